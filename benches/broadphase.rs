@@ -0,0 +1,56 @@
+use cgmath::MetricSpace;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use gravinyon::UniformGrid;
+use rand::{Rng, SeedableRng};
+
+/// About the distance up to which objects of the game can interact.
+const INTERACTION_DISTANCE: f32 = 0.35;
+
+fn random_positions(count: usize) -> Vec<cgmath::Point2<f32>> {
+	let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+	(0..count)
+		.map(|_| cgmath::Point2 { x: rng.gen_range(-1.1..1.1), y: rng.gen_range(-0.5..0.5) })
+		.collect()
+}
+
+fn all_pairs(positions: &[cgmath::Point2<f32>]) -> usize {
+	let mut close_pairs = 0;
+	for a in 0..positions.len() {
+		for b in (a + 1)..positions.len() {
+			if positions[a].distance(positions[b]) < INTERACTION_DISTANCE {
+				close_pairs += 1;
+			}
+		}
+	}
+	close_pairs
+}
+
+fn grid_pairs(positions: &[cgmath::Point2<f32>]) -> usize {
+	let grid = UniformGrid::new(INTERACTION_DISTANCE, positions.iter().copied());
+	grid
+		.candidate_pairs()
+		.filter(|&(a, b)| positions[a].distance(positions[b]) < INTERACTION_DISTANCE)
+		.count()
+}
+
+fn broadphase(c: &mut Criterion) {
+	let mut group = c.benchmark_group("close_pairs");
+	for count in [100, 1000, 3000] {
+		let positions = random_positions(count);
+		assert_eq!(all_pairs(&positions), grid_pairs(&positions));
+		group.bench_with_input(
+			BenchmarkId::new("all_pairs", count),
+			&positions,
+			|b, positions| b.iter(|| all_pairs(positions)),
+		);
+		group.bench_with_input(
+			BenchmarkId::new("uniform_grid", count),
+			&positions,
+			|b, positions| b.iter(|| grid_pairs(positions)),
+		);
+	}
+	group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
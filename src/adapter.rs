@@ -0,0 +1,158 @@
+//! Choosing the adapter (GPU) to render with, the player having the last word.
+
+use crate::cli::Backend;
+
+/// Which adapter to use, as given with `--adapter`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterChoice {
+	/// The index in the list printed by `--list-adapters`.
+	Index(usize),
+	/// A part of the name, case insensitive.
+	Name(String),
+}
+
+impl AdapterChoice {
+	pub fn parse(index_or_name: &str) -> AdapterChoice {
+		match index_or_name.parse() {
+			Ok(index) => AdapterChoice::Index(index),
+			Err(_) => AdapterChoice::Name(index_or_name.to_string()),
+		}
+	}
+
+	fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+		match self {
+			AdapterChoice::Index(chosen_index) => index == *chosen_index,
+			AdapterChoice::Name(name_part) => {
+				(info.name.to_lowercase()).contains(&name_part.to_lowercase())
+			},
+		}
+	}
+}
+
+impl std::fmt::Display for AdapterChoice {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			AdapterChoice::Index(index) => write!(f, "index {index}"),
+			AdapterChoice::Name(name_part) => write!(f, "name \"{name_part}\""),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum AdapterError {
+	/// There is no adapter at all for the backends.
+	NoAdapter,
+	/// No adapter matches the choice, the available ones being listed with it.
+	NotFound { choice: AdapterChoice, available: Vec<String> },
+	/// The chosen adapter can't present to the window.
+	CannotPresent { description: String },
+}
+
+impl std::fmt::Display for AdapterError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			AdapterError::NoAdapter => write!(
+				f,
+				"no adapter was found, try another backend or check the graphics drivers"
+			),
+			AdapterError::NotFound { choice, available } => {
+				write!(f, "no adapter matches the {choice}")?;
+				if available.is_empty() {
+					write!(f, ", and there is no adapter at all")
+				} else {
+					write!(f, ", the available adapters are:")?;
+					for line in available {
+						write!(f, "\n  {line}")?;
+					}
+					Ok(())
+				}
+			},
+			AdapterError::CannotPresent { description } => write!(
+				f,
+				"the adapter {description} can't present to the window, try another adapter or backend"
+			),
+		}
+	}
+}
+
+impl std::error::Error for AdapterError {}
+
+/// The backends to consider, all of them by default.
+pub fn backends(backend: Option<Backend>) -> wgpu::Backends {
+	backend.map_or(wgpu::Backends::all(), wgpu::Backends::from)
+}
+
+/// The name of an adapter, followed by its backend, type and driver.
+pub fn describe_adapter(info: &wgpu::AdapterInfo) -> String {
+	let mut details = vec![
+		format!("{:?}", info.backend),
+		format!("{:?}", info.device_type),
+	];
+	let driver = format!("{} {}", info.driver, info.driver_info);
+	if !driver.trim().is_empty() {
+		details.push(driver.trim().to_string());
+	}
+	format!("{}  ({})", info.name, details.join(", "))
+}
+
+/// A line of the list of the adapters, as `--adapter` indexes them.
+fn adapter_line(index: usize, info: &wgpu::AdapterInfo) -> String {
+	format!("{index:>2}  {}", describe_adapter(info))
+}
+
+/// Prints the adapters of the backends, as `--adapter` indexes them.
+pub fn print_adapters(instance: &wgpu::Instance, backends: wgpu::Backends) {
+	let mut any = false;
+	for (index, adapter) in instance.enumerate_adapters(backends).enumerate() {
+		println!("{}", adapter_line(index, &adapter.get_info()));
+		any = true;
+	}
+	if !any {
+		println!("No adapter found");
+	}
+}
+
+/// The adapter to render to the surface with. Without a choice, a discrete GPU that can present
+/// to the surface is preferred, else the one wgpu recommends.
+pub fn select_adapter(
+	instance: &wgpu::Instance,
+	backends: wgpu::Backends,
+	surface: &wgpu::Surface,
+	choice: Option<&AdapterChoice>,
+) -> Result<wgpu::Adapter, AdapterError> {
+	let Some(choice) = choice else {
+		// Try to get a cool adapter first.
+		let adapter = instance.enumerate_adapters(backends).find(|adapter| {
+			let info = adapter.get_info();
+			info.device_type == wgpu::DeviceType::DiscreteGpu && adapter.is_surface_supported(surface)
+		});
+		// In case we didn't find any cool adapter, at least we can try to get a bad adapter.
+		let adapter = adapter.or_else(|| {
+			futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+				power_preference: wgpu::PowerPreference::HighPerformance,
+				compatible_surface: Some(surface),
+				force_fallback_adapter: false,
+			}))
+		});
+		return adapter.ok_or(AdapterError::NoAdapter);
+	};
+
+	let adapters: Vec<_> = instance.enumerate_adapters(backends).collect();
+	let Some((index, adapter)) = adapters
+		.iter()
+		.enumerate()
+		.find(|(index, adapter)| choice.matches(*index, &adapter.get_info()))
+	else {
+		let available = adapters
+			.iter()
+			.enumerate()
+			.map(|(index, adapter)| adapter_line(index, &adapter.get_info()))
+			.collect();
+		return Err(AdapterError::NotFound { choice: choice.clone(), available });
+	};
+	if !adapter.is_surface_supported(surface) {
+		let description = describe_adapter(&adapter.get_info());
+		return Err(AdapterError::CannotPresent { description });
+	}
+	Ok(adapters.into_iter().nth(index).unwrap())
+}
@@ -0,0 +1,96 @@
+/// Uniform grid over the playfield, used to find the pairs of objects that are close enough to
+/// maybe interact without comparing every object with every other object.
+///
+/// Each object is put in the cell that contains its center, so two objects that are at most
+/// `cell_size` apart are always in the same cell or in neighboring cells. The columns wrap around
+/// like the playfield does (obstacles leaving on one side come back on the other side), the first
+/// and the last columns being neighbors.
+pub struct UniformGrid {
+	cell_size: f32,
+	/// At least `cell_size`, so that a whole number of columns covers the width of the grid.
+	cell_width: f32,
+	columns: usize,
+	rows: usize,
+	/// The indices of the objects that are in each cell, row by row.
+	cells: Vec<Vec<usize>>,
+}
+
+impl UniformGrid {
+	/// The area covered by the grid, a bit bigger than the playfield (-1.1..1.1 × -0.5..0.5) as
+	/// shots can go a bit further before disappearing. Objects outside of it are put in the
+	/// nearest cell, which is still correct (only a bit slower if there are a lot of them).
+	const MIN: cgmath::Point2<f32> = cgmath::Point2 { x: -1.1, y: -0.6 };
+	const MAX: cgmath::Point2<f32> = cgmath::Point2 { x: 1.1, y: 0.6 };
+
+	/// The `cell_size` should be at least the distance up to which objects can interact.
+	pub fn new(
+		cell_size: f32,
+		positions: impl IntoIterator<Item = cgmath::Point2<f32>>,
+	) -> UniformGrid {
+		let width = UniformGrid::MAX.x - UniformGrid::MIN.x;
+		let mut columns = (width / cell_size) as usize;
+		if width / (columns as f32) < cell_size {
+			// Rounding errors.
+			columns -= 1;
+		}
+		// With two columns, each would be the neighbor of the other on both sides.
+		let columns = if columns < 3 { 1 } else { columns };
+		let cell_width = width / columns as f32;
+		let rows = (((UniformGrid::MAX.y - UniformGrid::MIN.y) / cell_size).ceil() as usize).max(1);
+		let mut grid = UniformGrid {
+			cell_size,
+			cell_width,
+			columns,
+			rows,
+			cells: vec![Vec::new(); columns * rows],
+		};
+		for (index, position) in positions.into_iter().enumerate() {
+			let (column, row) = grid.cell_coords(position);
+			grid.cells[row * columns + column].push(index);
+		}
+		grid
+	}
+
+	fn cell_coords(&self, position: cgmath::Point2<f32>) -> (usize, usize) {
+		// Casting a NaN or a negative float to `usize` gives 0, that is some cell like any other.
+		let column = ((position.x - UniformGrid::MIN.x) / self.cell_width) as usize;
+		let row = ((position.y - UniformGrid::MIN.y) / self.cell_size) as usize;
+		(column.min(self.columns - 1), row.min(self.rows - 1))
+	}
+
+	/// All the pairs `(a, b)` (with `a < b`) of indices of objects that are in the same cell or
+	/// in neighboring cells. Every pair of objects that are at most `cell_size` apart is in there,
+	/// be it directly or around the wrapping of the x axis (along with some pairs of objects that
+	/// are further apart).
+	pub fn candidate_pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+		(0..self.rows).flat_map(move |row| {
+			(0..self.columns).flat_map(move |column| {
+				let cell = &self.cells[row * self.columns + column];
+				// Pairs inside the cell.
+				let inside_pairs = cell
+					.iter()
+					.enumerate()
+					.flat_map(move |(i, &a)| cell[(i + 1)..].iter().map(move |&b| (a.min(b), a.max(b))));
+				// Pairs with half of the neighbors, so that each pair of cells is visited once.
+				let neighbors = [(1, 0), (-1, 1), (0, 1), (1, 1)].into_iter().filter_map(
+					move |(dx, dy): (isize, isize)| {
+						if dx != 0 && self.columns == 1 {
+							return None;
+						}
+						let neighbor_column =
+							(column as isize + dx).rem_euclid(self.columns as isize) as usize;
+						let neighbor_row = row.checked_add_signed(dy)?;
+						(neighbor_row < self.rows)
+							.then(|| &self.cells[neighbor_row * self.columns + neighbor_column])
+					},
+				);
+				let neighbor_pairs = neighbors.flat_map(move |neighbor_cell| {
+					cell
+						.iter()
+						.flat_map(move |&a| neighbor_cell.iter().map(move |&b| (a.min(b), a.max(b))))
+				});
+				inside_pairs.chain(neighbor_pairs)
+			})
+		})
+	}
+}
@@ -0,0 +1,203 @@
+//! The command line interface.
+
+use std::{ffi::OsString, path::PathBuf};
+
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+
+use crate::{
+	adapter::AdapterChoice,
+	frame_pacing::{FrameLimiter, PresentMode},
+	level::Level,
+};
+
+/// Gravinyon, a little game about a ship pulled towards the cursor among obstacles.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+	/// The level to play, as a number (from 1) or the name of a bundled level. Without it,
+	/// the title screen lets the player pick a level.
+	#[arg(value_parser = parse_level, conflicts_with_all = ["level_file", "replay"])]
+	pub level: Option<Level>,
+
+	/// Play the level described by the given TOML file.
+	#[arg(long, value_name = "PATH", conflicts_with = "replay")]
+	pub level_file: Option<PathBuf>,
+
+	/// The seed of the game, random by default. Games played with a chosen seed have their own
+	/// high scores.
+	#[arg(long, conflicts_with = "replay")]
+	pub seed: Option<u64>,
+
+	/// The size of the window, maximized by default.
+	#[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_window_size)]
+	pub window_size: Option<(u32, u32)>,
+
+	/// Play in borderless fullscreen instead of in a window.
+	#[arg(long, conflicts_with = "window_size")]
+	pub fullscreen: bool,
+
+	/// The adapter (GPU) to render with, as an index in the list of `--list-adapters` or a part
+	/// of its name.
+	#[arg(long, value_name = "INDEX_OR_NAME", value_parser = parse_adapter_choice)]
+	pub adapter: Option<AdapterChoice>,
+
+	/// The graphics API to render with, all of them are considered by default.
+	#[arg(long, value_enum)]
+	pub backend: Option<Backend>,
+
+	/// Print the available adapters (of the backend, if given) and exit.
+	#[arg(long, conflicts_with = "adapter")]
+	pub list_adapters: bool,
+
+	/// How frames are presented, `fifo` by default (or as in the config file). If it is not
+	/// supported, the closest supported one is used.
+	#[arg(long, value_enum)]
+	pub present_mode: Option<PresentMode>,
+
+	/// Render at most that many frames per second (no limit by default, or as in the config file).
+	#[arg(long, value_name = "FPS", value_parser = parse_max_fps)]
+	pub max_fps: Option<f64>,
+
+	/// Play without sound (it can be turned back on while playing).
+	#[arg(long)]
+	pub mute: bool,
+
+	/// Play back the given replay file (its level, seed and inputs).
+	#[arg(long, value_name = "PATH")]
+	pub replay: Option<PathBuf>,
+
+	/// Record the first game played to the given replay file.
+	#[arg(long, value_name = "PATH")]
+	pub record: Option<PathBuf>,
+
+	/// Run the simulation without a window for the given number of steps, as fast as possible.
+	#[arg(long, value_name = "STEPS")]
+	pub headless: Option<u64>,
+
+	/// Save an image of the end of the headless run.
+	#[arg(long, value_name = "PATH", requires = "headless")]
+	pub screenshot: Option<PathBuf>,
+
+	/// Run headless for the given number of ticks (steps) and save an image of the end, the same
+	/// as `--headless <TICKS> --screenshot <PATH>`.
+	#[arg(
+		long,
+		num_args = 2,
+		value_names = ["TICKS", "PATH"],
+		conflicts_with_all = ["headless", "screenshot"]
+	)]
+	screenshot_after: Option<Vec<String>>,
+
+	/// The config file, instead of the one in the user's config directory.
+	#[arg(long, value_name = "PATH")]
+	pub config: Option<PathBuf>,
+
+	/// The name of the player, kept with their high scores.
+	#[arg(long)]
+	pub name: Option<String>,
+
+	/// Print the high scores and exit.
+	#[arg(long, exclusive = true)]
+	pub scores: bool,
+}
+
+impl Cli {
+	/// Parses the arguments of the program, exiting with an error (or the help) if they are not
+	/// valid.
+	pub fn parse_args() -> Cli {
+		Cli::try_parse_args_from(std::env::args_os()).unwrap_or_else(|error| error.exit())
+	}
+
+	/// Parses the given arguments (the first one being the program name), `--screenshot-after`
+	/// being turned into `--headless` and `--screenshot`.
+	pub fn try_parse_args_from<I, T>(args: I) -> Result<Cli, clap::Error>
+	where
+		I: IntoIterator<Item = T>,
+		T: Into<OsString> + Clone,
+	{
+		let mut cli = Cli::try_parse_from(args)?;
+		if let Some([ticks, path]) = cli.screenshot_after.take().as_deref() {
+			let ticks = ticks.parse().map_err(|_| {
+				Cli::command().error(
+					ErrorKind::ValueValidation,
+					format!("invalid value '{ticks}' for '--screenshot-after <TICKS> <PATH>': expected a number of ticks"),
+				)
+			})?;
+			cli.headless = Some(ticks);
+			cli.screenshot = Some(PathBuf::from(path));
+		}
+		Ok(cli)
+	}
+
+	/// Exits with an error about the arguments, the same way as when parsing them fails (with the
+	/// usage). Failures that are not about the arguments are not reported this way.
+	pub fn exit_with_error(message: impl std::fmt::Display) -> ! {
+		Cli::command()
+			.error(ErrorKind::ValueValidation, message)
+			.exit()
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Backend {
+	Vulkan,
+	Metal,
+	Dx12,
+	Dx11,
+	/// OpenGL (or OpenGL ES).
+	Gl,
+}
+
+impl From<Backend> for wgpu::Backends {
+	fn from(backend: Backend) -> wgpu::Backends {
+		match backend {
+			Backend::Vulkan => wgpu::Backends::VULKAN,
+			Backend::Metal => wgpu::Backends::METAL,
+			Backend::Dx12 => wgpu::Backends::DX12,
+			Backend::Dx11 => wgpu::Backends::DX11,
+			Backend::Gl => wgpu::Backends::GL,
+		}
+	}
+}
+
+fn parse_adapter_choice(index_or_name: &str) -> Result<AdapterChoice, String> {
+	if index_or_name.is_empty() {
+		return Err("expected an index or a part of the name of an adapter".to_string());
+	}
+	Ok(AdapterChoice::parse(index_or_name))
+}
+
+fn parse_max_fps(max_fps: &str) -> Result<f64, String> {
+	match max_fps.parse::<f64>() {
+		Ok(max_fps) if max_fps.is_finite() && max_fps >= FrameLimiter::MIN_FPS => Ok(max_fps),
+		_ => Err(format!(
+			"expected a number of frames per second of at least {}",
+			FrameLimiter::MIN_FPS
+		)),
+	}
+}
+
+fn parse_level(number_or_name: &str) -> Result<Level, String> {
+	Level::find_bundled(number_or_name).ok_or_else(|| {
+		let names: Vec<_> = Level::bundled()
+			.into_iter()
+			.map(|level| level.name)
+			.collect();
+		format!(
+			"expected a number from 1 to {} or one of {}",
+			names.len(),
+			names.join(", ")
+		)
+	})
+}
+
+fn parse_window_size(size: &str) -> Result<(u32, u32), String> {
+	let error = || "expected a size like 1280x720".to_string();
+	let (width, height) = size.split_once('x').ok_or_else(error)?;
+	let width: u32 = width.parse().map_err(|_| error())?;
+	let height: u32 = height.parse().map_err(|_| error())?;
+	if width == 0 || height == 0 {
+		return Err("the width and height must not be zero".to_string());
+	}
+	Ok((width, height))
+}
@@ -0,0 +1,76 @@
+use cgmath::{InnerSpace, MetricSpace};
+
+use crate::mesh::WhichMesh;
+
+/// A triangle on the screen plane.
+pub type Triangle2 = [cgmath::Point2<f32>; 3];
+
+/// A mesh placed somewhere, seen as the shape it covers on the screen plane. It is placed the
+/// same way as the object shader places instances, so that it covers exactly what is rendered.
+pub struct PlacedMesh {
+	pub mesh: WhichMesh,
+	pub position: cgmath::Point2<f32>,
+	pub angle: f32,
+	pub scale: f32,
+}
+
+impl PlacedMesh {
+	fn bounding_radius(&self) -> f32 {
+		self.mesh.bounding_radius() * self.scale
+	}
+
+	fn triangles(&self) -> impl Iterator<Item = Triangle2> + '_ {
+		let (sin, cos) = f32::sin_cos(self.angle);
+		let place = move |[x, y, _z]: [f32; 3]| {
+			let rotated = cgmath::Vector2 { x: x * cos - y * sin, y: x * sin + y * cos };
+			self.position + rotated * self.scale
+		};
+		self
+			.mesh
+			.triangles()
+			.iter()
+			.map(move |[a, b, c]| [place(*a), place(*b), place(*c)])
+	}
+
+	pub fn collides_with(&self, other: &PlacedMesh) -> bool {
+		// Cheap check first, most pairs are far apart.
+		let distance = self.position.distance(other.position);
+		if distance >= self.bounding_radius() + other.bounding_radius() {
+			return false;
+		}
+
+		self.triangles().any(|triangle| {
+			other
+				.triangles()
+				.any(|other_triangle| triangles_intersect(&triangle, &other_triangle))
+		})
+	}
+}
+
+/// Separating axis test, two triangles do not intersect if and only if one of their edges is
+/// along a line that separates them. Triangles that only touch do not intersect.
+pub fn triangles_intersect(a: &Triangle2, b: &Triangle2) -> bool {
+	let edge_normals = |triangle: &Triangle2| {
+		let triangle = *triangle;
+		(0..3).map(move |i| {
+			let edge = triangle[(i + 1) % 3] - triangle[i];
+			cgmath::Vector2 { x: -edge.y, y: edge.x }
+		})
+	};
+	let project = |triangle: &Triangle2, axis: cgmath::Vector2<f32>| {
+		let projections =
+			triangle.map(|vertex| cgmath::Vector2 { x: vertex.x, y: vertex.y }.dot(axis));
+		let min = projections.into_iter().fold(f32::INFINITY, f32::min);
+		let max = projections.into_iter().fold(f32::NEG_INFINITY, f32::max);
+		(min, max)
+	};
+	edge_normals(a).chain(edge_normals(b)).all(|axis| {
+		if axis == cgmath::Vector2::new(0.0, 0.0) {
+			// A degenerate edge does not separate anything.
+			return true;
+		}
+		let (a_min, a_max) = project(a, axis);
+		let (b_min, b_max) = project(b, axis);
+		a_min < b_max && b_min < a_max
+	})
+}
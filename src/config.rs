@@ -0,0 +1,131 @@
+//! The user config file, in TOML, where the settings that are not given on the command line
+//! are kept. Everything in there is optional, missing settings having default values.
+//!
+//! ```toml
+//! [controls]
+//! fire = ["MouseLeft", "Space"]
+//! impulse = ["MouseRight", "LShift"]
+//!
+//! [graphics]
+//! present_mode = "mailbox"
+//! max_fps = 144
+//!
+//! [sound]
+//! master_volume = 0.8
+//! effects_volume = 0.5
+//! music_volume = 0.3
+//! mute = false
+//!
+//! [sound.files]
+//! explosion = "/home/me/sounds/boom.ogg"
+//! ```
+
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+	controls::Bindings,
+	frame_pacing::{FrameLimiter, PresentMode},
+	sound::SoundSettings,
+};
+
+#[derive(Debug)]
+pub enum ConfigError {
+	Io(std::io::Error),
+	Parse(toml::de::Error),
+	/// The config parses fine but some value does not make sense.
+	Invalid(String),
+}
+
+impl std::fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ConfigError::Io(error) => write!(f, "{error}"),
+			ConfigError::Parse(error) => write!(f, "{error}"),
+			ConfigError::Invalid(reason) => write!(f, "invalid config: {reason}"),
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+	fn from(error: std::io::Error) -> ConfigError {
+		ConfigError::Io(error)
+	}
+}
+
+impl From<toml::de::Error> for ConfigError {
+	fn from(error: toml::de::Error) -> ConfigError {
+		ConfigError::Parse(error)
+	}
+}
+
+/// The config file as it is written, before validation.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+	#[serde(default)]
+	controls: BTreeMap<String, Vec<String>>,
+	#[serde(default)]
+	graphics: Graphics,
+	#[serde(default)]
+	sound: SoundSettings,
+}
+
+/// The `graphics` table, the command line having the last word on these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Graphics {
+	pub present_mode: Option<PresentMode>,
+	/// The frame rate cap, if any.
+	pub max_fps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+	pub bindings: Bindings,
+	pub graphics: Graphics,
+	pub sound: SoundSettings,
+}
+
+impl Config {
+	/// Where the config is by default, if the platform has a config directory.
+	pub fn default_path() -> Option<PathBuf> {
+		Some(dirs::config_dir()?.join("gravinyon").join("config.toml"))
+	}
+
+	/// Loads the config from the given file, a missing file giving the default config.
+	pub fn load(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+		match std::fs::read_to_string(path) {
+			Ok(source) => Config::parse(&source),
+			Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	pub fn parse(source: &str) -> Result<Config, ConfigError> {
+		let config_file: ConfigFile = toml::from_str(source)?;
+		let bindings = Bindings::from_config(&config_file.controls).map_err(ConfigError::Invalid)?;
+		let graphics = config_file.graphics;
+		if let Some(max_fps) = graphics.max_fps {
+			if !(max_fps.is_finite() && max_fps >= FrameLimiter::MIN_FPS) {
+				return Err(ConfigError::Invalid(format!(
+					"`max_fps` in `graphics` must be a number of at least {}, not {max_fps}",
+					FrameLimiter::MIN_FPS
+				)));
+			}
+		}
+		let sound = config_file.sound;
+		if let Err(volume_name) = sound.validate() {
+			return Err(ConfigError::Invalid(format!(
+				"`{volume_name}` in `sound` must be between 0 and 1"
+			)));
+		}
+		Ok(Config { bindings, graphics, sound })
+	}
+}
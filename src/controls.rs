@@ -0,0 +1,337 @@
+//! Translation of what the player does with their devices into game actions, and then into
+//! the `Input` of each step of the simulation.
+//!
+//! Two control schemes are supported at the same time:
+//! - the mouse, the cursor being where the ship is pulled towards and aims at;
+//! - the keyboard, a virtual aim point being moved around with WASD or the arrows keys.
+//!
+//! The buttons of each action can be changed in the config file (see `Bindings::from_config`).
+//!
+//! There is no separate thrust control, the ship is always pulled towards the aim point
+//! (be it the cursor or the virtual aim point), so aiming is also thrusting.
+
+use std::collections::{BTreeMap, HashMap};
+
+use cgmath::InnerSpace;
+use serde::{de::IntoDeserializer, Deserialize};
+pub use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::world::Input;
+
+/// Something the player can do, whatever the device used to do it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+	/// Shoot for as long as it is held, or restart if the game is over.
+	Fire,
+	Impulse,
+	Restart,
+	Pause,
+	Quit,
+	/// Turn the sound off, or back on.
+	Mute,
+	AimUp,
+	AimDown,
+	AimLeft,
+	AimRight,
+}
+
+impl Action {
+	pub const ALL: [Action; 10] = [
+		Action::Fire,
+		Action::Impulse,
+		Action::Restart,
+		Action::Pause,
+		Action::Quit,
+		Action::Mute,
+		Action::AimUp,
+		Action::AimDown,
+		Action::AimLeft,
+		Action::AimRight,
+	];
+
+	/// The name of the action in the config file.
+	pub fn name(self) -> &'static str {
+		match self {
+			Action::Fire => "fire",
+			Action::Impulse => "impulse",
+			Action::Restart => "restart",
+			Action::Pause => "pause",
+			Action::Quit => "quit",
+			Action::Mute => "mute",
+			Action::AimUp => "aim_up",
+			Action::AimDown => "aim_down",
+			Action::AimLeft => "aim_left",
+			Action::AimRight => "aim_right",
+		}
+	}
+}
+
+/// A key or a mouse button.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Button {
+	Key(VirtualKeyCode),
+	Mouse(MouseButton),
+}
+
+impl Button {
+	/// Parses the name of a button as written in the config file, that is `MouseLeft`,
+	/// `MouseRight`, `MouseMiddle`, `Mouse<number>` or the name of a key as in `VirtualKeyCode`.
+	pub fn parse(name: &str) -> Option<Button> {
+		match name {
+			"MouseLeft" => Some(Button::Mouse(MouseButton::Left)),
+			"MouseRight" => Some(Button::Mouse(MouseButton::Right)),
+			"MouseMiddle" => Some(Button::Mouse(MouseButton::Middle)),
+			_ => match name.strip_prefix("Mouse").map(str::parse) {
+				Some(Ok(number)) => Some(Button::Mouse(MouseButton::Other(number))),
+				_ => {
+					let name = IntoDeserializer::<serde::de::value::Error>::into_deserializer(name);
+					VirtualKeyCode::deserialize(name).ok().map(Button::Key)
+				},
+			},
+		}
+	}
+
+	/// The name of the button, as parsed by `Button::parse`.
+	pub fn name(self) -> String {
+		match self {
+			Button::Key(key) => format!("{key:?}"),
+			Button::Mouse(MouseButton::Left) => "MouseLeft".to_string(),
+			Button::Mouse(MouseButton::Right) => "MouseRight".to_string(),
+			Button::Mouse(MouseButton::Middle) => "MouseMiddle".to_string(),
+			Button::Mouse(MouseButton::Other(number)) => format!("Mouse{number}"),
+		}
+	}
+}
+
+/// What the player did, independently of the windowing library so that it can be synthesized.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputEvent {
+	/// The cursor moved to the given position, in the same coordinates as the objects.
+	CursorMoved(cgmath::Point2<f32>),
+	Button {
+		button: Button,
+		pressed: bool,
+	},
+}
+
+/// Which action each button triggers.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+	actions: HashMap<Button, Action>,
+}
+
+impl Default for Bindings {
+	fn default() -> Bindings {
+		Bindings { actions: Bindings::DEFAULT.into_iter().collect() }
+	}
+}
+
+impl Bindings {
+	const DEFAULT: [(Button, Action); 16] = {
+		use VirtualKeyCode as Key;
+		[
+			(Button::Mouse(MouseButton::Left), Action::Fire),
+			(Button::Mouse(MouseButton::Right), Action::Impulse),
+			(Button::Key(Key::Space), Action::Fire),
+			(Button::Key(Key::LShift), Action::Impulse),
+			(Button::Key(Key::Return), Action::Restart),
+			(Button::Key(Key::P), Action::Pause),
+			(Button::Key(Key::Escape), Action::Quit),
+			(Button::Key(Key::M), Action::Mute),
+			(Button::Key(Key::W), Action::AimUp),
+			(Button::Key(Key::Up), Action::AimUp),
+			(Button::Key(Key::S), Action::AimDown),
+			(Button::Key(Key::Down), Action::AimDown),
+			(Button::Key(Key::A), Action::AimLeft),
+			(Button::Key(Key::Left), Action::AimLeft),
+			(Button::Key(Key::D), Action::AimRight),
+			(Button::Key(Key::Right), Action::AimRight),
+		]
+	};
+
+	/// Makes bindings from the `controls` table of the config file, that gives the names of the
+	/// buttons of some actions (like `fire = ["MouseLeft", "Space"]`). The actions that are not in
+	/// there keep their default buttons, unless these are taken by another action.
+	pub fn from_config(table: &BTreeMap<String, Vec<String>>) -> Result<Bindings, String> {
+		let mut actions = HashMap::new();
+		for (action_name, button_names) in table {
+			let Some(action) = Action::ALL
+				.into_iter()
+				.find(|action| action.name() == action_name)
+			else {
+				let action_names: Vec<_> = Action::ALL.iter().map(|action| action.name()).collect();
+				return Err(format!(
+					"unknown action `{action_name}` in `controls`, expected one of {}",
+					action_names.join(", ")
+				));
+			};
+			for button_name in button_names {
+				let Some(button) = Button::parse(button_name) else {
+					return Err(format!(
+						"unknown button `{button_name}` for action `{action_name}` in `controls`"
+					));
+				};
+				if let Some(other_action) = actions.insert(button, action) {
+					if other_action != action {
+						return Err(format!(
+							"button `{button_name}` is bound to both `{}` and `{action_name}` in `controls`",
+							other_action.name()
+						));
+					}
+				}
+			}
+		}
+		for (button, action) in Bindings::DEFAULT {
+			if table.contains_key(action.name()) {
+				continue;
+			}
+			match actions.get(&button) {
+				Some(other_action) => log::warn!(
+					"Button `{}` is not bound to `{}` by default as it is bound to `{}`",
+					button.name(),
+					action.name(),
+					other_action.name()
+				),
+				None => {
+					actions.insert(button, action);
+				},
+			}
+		}
+		Ok(Bindings { actions })
+	}
+
+	pub fn action(&self, button: Button) -> Option<Action> {
+		self.actions.get(&button).copied()
+	}
+
+	/// The buttons bound to the given action, sorted by name.
+	pub fn buttons(&self, action: Action) -> Vec<Button> {
+		let mut buttons: Vec<Button> = self
+			.actions
+			.iter()
+			.filter(|(_button, &button_action)| button_action == action)
+			.map(|(&button, _action)| button)
+			.collect();
+		buttons.sort_by_key(|button| button.name());
+		buttons
+	}
+}
+
+/// Keeps track of the state of the actions, as the input events come, to give the input of
+/// each step of the simulation.
+pub struct Controller {
+	bindings: Bindings,
+	/// The buttons being held down, as several buttons can trigger the same action.
+	held_buttons: Vec<Button>,
+	aim_position: cgmath::Point2<f32>,
+	impulse_requested: bool,
+	restart_requested: bool,
+	/// The actions of the buttons pressed since the last call to `take_pressed_actions`.
+	pressed_actions: Vec<Action>,
+}
+
+impl Controller {
+	/// How far the virtual aim point moves in one step when an aim key is held.
+	const AIM_SPEED: f32 = 0.02;
+
+	pub fn new(bindings: Bindings) -> Controller {
+		Controller {
+			bindings,
+			held_buttons: Vec::new(),
+			aim_position: Input::default().cursor_position,
+			impulse_requested: false,
+			restart_requested: false,
+			pressed_actions: Vec::new(),
+		}
+	}
+
+	fn is_held(&self, action: Action) -> bool {
+		self
+			.held_buttons
+			.iter()
+			.any(|&button| self.bindings.action(button) == Some(action))
+	}
+
+	pub fn handle_event(&mut self, event: InputEvent) {
+		match event {
+			InputEvent::CursorMoved(position) => self.aim_position = position,
+			InputEvent::Button { button, pressed: true } => {
+				// Key repeat gives presses of keys already held, these are not new presses.
+				if self.held_buttons.contains(&button) {
+					return;
+				}
+				self.held_buttons.push(button);
+				let Some(action) = self.bindings.action(button) else {
+					return;
+				};
+				self.pressed_actions.push(action);
+				match action {
+					Action::Fire => self.restart_requested = true,
+					Action::Impulse => self.impulse_requested = true,
+					Action::Restart => self.restart_requested = true,
+					_ => {},
+				}
+			},
+			InputEvent::Button { button, pressed: false } => {
+				self
+					.held_buttons
+					.retain(|&held_button| held_button != button);
+			},
+		}
+	}
+
+	/// Forgets about the held buttons, to be called when the events about them being released
+	/// may be missed (like when the window loses focus).
+	pub fn release_all(&mut self) {
+		self.held_buttons.clear();
+	}
+
+	/// The actions of the buttons pressed since the last call, in order.
+	pub fn take_pressed_actions(&mut self) -> Vec<Action> {
+		std::mem::take(&mut self.pressed_actions)
+	}
+
+	/// Forgets about the single presses (like the impulse) not yet given to a step, to be called
+	/// when the simulation is not running so that they don't pile up until it runs again.
+	pub fn clear_step_requests(&mut self) {
+		self.impulse_requested = false;
+		self.restart_requested = false;
+	}
+
+	/// Where the ship is pulled towards and aims at.
+	pub fn aim_position(&self) -> cgmath::Point2<f32> {
+		self.aim_position
+	}
+
+	/// The input for the next step of the simulation. The single presses (like the impulse)
+	/// are only given once.
+	pub fn next_step_input(&mut self) -> Input {
+		let mut aim_motion = cgmath::Vector2::<f32> { x: 0.0, y: 0.0 };
+		if self.is_held(Action::AimUp) {
+			aim_motion.y += 1.0;
+		}
+		if self.is_held(Action::AimDown) {
+			aim_motion.y -= 1.0;
+		}
+		if self.is_held(Action::AimLeft) {
+			aim_motion.x -= 1.0;
+		}
+		if self.is_held(Action::AimRight) {
+			aim_motion.x += 1.0;
+		}
+		if aim_motion != (cgmath::Vector2 { x: 0.0, y: 0.0 }) {
+			self.aim_position += aim_motion.normalize() * Controller::AIM_SPEED;
+			self.aim_position.x = self.aim_position.x.clamp(-1.0, 1.0);
+			self.aim_position.y = self.aim_position.y.clamp(-0.5, 0.5);
+		}
+
+		Input {
+			cursor_position: self.aim_position,
+			shooting: self.is_held(Action::Fire),
+			impulse: std::mem::take(&mut self.impulse_requested),
+			// Restarting only has an effect when the game is over, so firing can also be a request
+			// to restart without getting in the way.
+			restart: std::mem::take(&mut self.restart_requested),
+		}
+	}
+}
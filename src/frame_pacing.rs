@@ -0,0 +1,102 @@
+//! When frames are shown: the present mode of the surface, and an optional frame rate cap.
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresentMode {
+	/// Wait for the vertical blank, no tearing.
+	Fifo,
+	/// Replace the frame waiting for the vertical blank, no tearing.
+	Mailbox,
+	/// Present right away, tearing may happen.
+	Immediate,
+}
+
+impl PresentMode {
+	/// The present mode to use among the ones the surface supports. If this one is not supported,
+	/// the closest one that is supported is used instead.
+	pub fn choose(self, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+		use wgpu::PresentMode as Mode;
+		let preferences: &[Mode] = match self {
+			PresentMode::Fifo => &[
+				Mode::Fifo,
+				Mode::FifoRelaxed,
+				Mode::Mailbox,
+				Mode::Immediate,
+			],
+			PresentMode::Mailbox => &[
+				Mode::Mailbox,
+				Mode::Immediate,
+				Mode::Fifo,
+				Mode::FifoRelaxed,
+			],
+			PresentMode::Immediate => &[
+				Mode::Immediate,
+				Mode::Mailbox,
+				Mode::Fifo,
+				Mode::FifoRelaxed,
+			],
+		};
+		let present_mode = preferences
+			.iter()
+			.copied()
+			.find(|present_mode| supported.contains(present_mode))
+			.or_else(|| supported.first().copied())
+			.unwrap_or(Mode::Fifo);
+		if present_mode != preferences[0] {
+			log::warn!(
+				"The present mode {:?} is not supported, using {present_mode:?} instead",
+				preferences[0]
+			);
+		}
+		present_mode
+	}
+}
+
+/// Keeps frames from coming more often than some rate, in software (independently of the present
+/// mode, that may not wait at all).
+pub struct FrameLimiter {
+	frame_duration: Duration,
+	/// When the next frame can start, if there was a frame already.
+	next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+	/// The lowest cap allowed, the settings are checked against it.
+	pub const MIN_FPS: f64 = 1.0;
+
+	/// A cap below `FrameLimiter::MIN_FPS` (or that is not a number) is taken as that minimum.
+	pub fn new(max_fps: f64) -> FrameLimiter {
+		let longest_frame_duration = Duration::from_secs_f64(1.0 / FrameLimiter::MIN_FPS);
+		let frame_duration = Duration::try_from_secs_f64(1.0 / max_fps)
+			.map_or(longest_frame_duration, |duration| {
+				duration.min(longest_frame_duration)
+			});
+		FrameLimiter { frame_duration, next_frame: None }
+	}
+
+	/// Starts a frame if it is time for it, else gives when the next frame can start.
+	pub fn start_frame(&mut self, now: Instant) -> Result<(), Instant> {
+		match self.next_frame {
+			Some(next_frame) if now < next_frame => Err(next_frame),
+			Some(next_frame) => {
+				// Frames are scheduled at a regular pace, but a frame that is very late does not
+				// allow for a burst of frames to catch up afterwards.
+				let next_frame = next_frame + self.frame_duration;
+				self.next_frame = Some(if next_frame <= now {
+					now + self.frame_duration
+				} else {
+					next_frame
+				});
+				Ok(())
+			},
+			None => {
+				self.next_frame = Some(now + self.frame_duration);
+				Ok(())
+			},
+		}
+	}
+}
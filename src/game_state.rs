@@ -0,0 +1,342 @@
+//! What the game is doing at the top level (showing the title screen, playing, being paused),
+//! and how it goes from one state to another as the player acts.
+
+use crate::{
+	controls::Action,
+	level::Level,
+	render::{RenderedText, TextAlignment},
+	scores::HighScores,
+	world::World,
+};
+
+/// Something to do outside of the state machine, as asked by the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StateCommand {
+	/// Start a new game of the level of the given index in the level select.
+	StartGame(usize),
+	/// Stop the current game, to go back to the title screen. The state is left as it is,
+	/// the title screen is to be made with `LevelSelect::new` to show up to date best scores.
+	LeaveGame,
+	Quit,
+}
+
+/// What the title screen shows about a level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelEntry {
+	pub name: String,
+	pub description: String,
+	/// The best score of the games played with a random seed.
+	pub best_score: Option<u32>,
+}
+
+/// The list of the levels of the title screen, one of them being selected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelSelect {
+	pub entries: Vec<LevelEntry>,
+	pub selected: usize,
+}
+
+impl LevelSelect {
+	const NAME_HEIGHT: f32 = 0.035;
+	const DESCRIPTION_HEIGHT: f32 = 0.02;
+	const ENTRY_SPACING: f32 = 0.12;
+	/// Where the first entry is.
+	const TOP_Y: f32 = 0.18;
+	const LEFT_X: f32 = -0.55;
+	const RIGHT_X: f32 = 0.55;
+	const DESCRIPTION_COLOR: [f32; 3] = [0.45, 0.45, 0.55];
+
+	pub fn new(levels: &[Level], high_scores: &HighScores, selected: usize) -> LevelSelect {
+		let entries = levels
+			.iter()
+			.map(|level| LevelEntry {
+				name: level.name.clone(),
+				description: level.description.clone(),
+				best_score: high_scores.best(&level.high_scores_key(), None),
+			})
+			.collect();
+		LevelSelect { entries, selected }
+	}
+
+	fn entry_y(index: usize) -> f32 {
+		LevelSelect::TOP_Y - index as f32 * LevelSelect::ENTRY_SPACING
+	}
+
+	/// The entry at the given position (in the same coordinates as the objects), if any.
+	fn entry_at(&self, position: cgmath::Point2<f32>) -> Option<usize> {
+		(0..self.entries.len()).find(|&index| {
+			let y = LevelSelect::entry_y(index);
+			let margin = LevelSelect::ENTRY_SPACING - LevelSelect::NAME_HEIGHT;
+			(y - margin / 2.0..y + LevelSelect::NAME_HEIGHT + margin / 2.0).contains(&position.y)
+				&& position.x.abs() < LevelSelect::RIGHT_X + 0.1
+		})
+	}
+
+	fn texts(&self) -> Vec<RenderedText> {
+		let mut texts = Vec::new();
+		for (index, entry) in self.entries.iter().enumerate() {
+			let y = LevelSelect::entry_y(index);
+			let selected = index == self.selected;
+			let color = if selected {
+				Menu::SELECTED_COLOR
+			} else {
+				Menu::COLOR
+			};
+			let line = |text: String, x: f32, alignment: TextAlignment| RenderedText {
+				text,
+				position: (x, y).into(),
+				alignment,
+				height: LevelSelect::NAME_HEIGHT,
+				color,
+			};
+			if selected {
+				texts.push(line(
+					">".to_string(),
+					LevelSelect::LEFT_X - 0.06,
+					TextAlignment::Left,
+				));
+			}
+			texts.push(line(
+				format!("{}  {}", index + 1, entry.name),
+				LevelSelect::LEFT_X,
+				TextAlignment::Left,
+			));
+			if let Some(best_score) = entry.best_score {
+				texts.push(line(
+					format!("Best {best_score}"),
+					LevelSelect::RIGHT_X,
+					TextAlignment::Right,
+				));
+			}
+			texts.push(RenderedText {
+				text: entry.description.clone(),
+				position: (LevelSelect::LEFT_X, y - LevelSelect::NAME_HEIGHT).into(),
+				alignment: TextAlignment::Left,
+				height: LevelSelect::DESCRIPTION_HEIGHT,
+				color: LevelSelect::DESCRIPTION_COLOR,
+			});
+		}
+		texts
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuItem {
+	Resume,
+	BackToTitle,
+	Quit,
+}
+
+impl MenuItem {
+	fn label(self) -> &'static str {
+		match self {
+			MenuItem::Resume => "Resume",
+			MenuItem::BackToTitle => "Back to title",
+			MenuItem::Quit => "Quit",
+		}
+	}
+}
+
+/// A list of items, one of them being selected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Menu {
+	pub items: Vec<MenuItem>,
+	pub selected: usize,
+}
+
+impl Menu {
+	const ITEM_HEIGHT: f32 = 0.04;
+	const ITEM_SPACING: f32 = 0.07;
+	/// Where the first item is.
+	const TOP_Y: f32 = 0.05;
+	const COLOR: [f32; 3] = [0.6, 0.6, 0.7];
+	const SELECTED_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+	fn pause() -> Menu {
+		Menu {
+			items: vec![MenuItem::Resume, MenuItem::BackToTitle, MenuItem::Quit],
+			selected: 0,
+		}
+	}
+
+	fn item_y(index: usize) -> f32 {
+		Menu::TOP_Y - index as f32 * Menu::ITEM_SPACING
+	}
+
+	/// The item at the given position (in the same coordinates as the objects), if any.
+	fn item_at(&self, position: cgmath::Point2<f32>) -> Option<usize> {
+		(0..self.items.len()).find(|&index| {
+			let y = Menu::item_y(index);
+			let margin = (Menu::ITEM_SPACING - Menu::ITEM_HEIGHT) / 2.0;
+			(y - margin..y + Menu::ITEM_HEIGHT + margin).contains(&position.y)
+				&& position.x.abs() < 0.5
+		})
+	}
+
+	fn texts(&self) -> Vec<RenderedText> {
+		self
+			.items
+			.iter()
+			.enumerate()
+			.map(|(index, item)| {
+				let selected = index == self.selected;
+				RenderedText {
+					text: if selected {
+						format!("> {} <", item.label())
+					} else {
+						item.label().to_string()
+					},
+					position: (0.0, Menu::item_y(index)).into(),
+					alignment: TextAlignment::Center,
+					height: Menu::ITEM_HEIGHT,
+					color: if selected {
+						Menu::SELECTED_COLOR
+					} else {
+						Menu::COLOR
+					},
+				}
+			})
+			.collect()
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameState {
+	Title(LevelSelect),
+	Playing,
+	Paused(Menu),
+	GameOver,
+}
+
+impl GameState {
+	/// Is the simulation running (the game being over does not stop it, as it waits for a
+	/// restart and replays must see the same steps as when they were recorded).
+	pub fn is_simulating(&self) -> bool {
+		matches!(self, GameState::Playing | GameState::GameOver)
+	}
+
+	/// Pauses the game, if there is one going on.
+	pub fn pause(&mut self) {
+		if self.is_simulating() {
+			*self = GameState::Paused(Menu::pause());
+		}
+	}
+
+	/// Follows the world going in and out of being game over.
+	pub fn follow_world(&mut self, world: &World) {
+		match self {
+			GameState::Playing if world.is_game_over() => *self = GameState::GameOver,
+			GameState::GameOver if !world.is_game_over() => *self = GameState::Playing,
+			_ => {},
+		}
+	}
+
+	/// Changes the state according to a button press of the player.
+	pub fn handle_action(&mut self, action: Action) -> Option<StateCommand> {
+		match self {
+			GameState::Title(level_select) => match action {
+				Action::Fire | Action::Restart if !level_select.entries.is_empty() => {
+					let level_index = level_select.selected;
+					*self = GameState::Playing;
+					Some(StateCommand::StartGame(level_index))
+				},
+				Action::AimUp => {
+					level_select.selected = level_select.selected.saturating_sub(1);
+					None
+				},
+				Action::AimDown => {
+					level_select.selected =
+						(level_select.selected + 1).min(level_select.entries.len().saturating_sub(1));
+					None
+				},
+				Action::Quit => Some(StateCommand::Quit),
+				_ => None,
+			},
+			GameState::Playing => {
+				if matches!(action, Action::Pause | Action::Quit) {
+					self.pause();
+				}
+				None
+			},
+			GameState::GameOver => match action {
+				Action::Pause => {
+					self.pause();
+					None
+				},
+				Action::Quit => Some(StateCommand::LeaveGame),
+				_ => None,
+			},
+			GameState::Paused(menu) => match action {
+				Action::Pause | Action::Quit => {
+					*self = GameState::Playing;
+					None
+				},
+				Action::AimUp => {
+					menu.selected = menu.selected.saturating_sub(1);
+					None
+				},
+				Action::AimDown => {
+					menu.selected = (menu.selected + 1).min(menu.items.len() - 1);
+					None
+				},
+				Action::Fire | Action::Restart => match menu.items[menu.selected] {
+					MenuItem::Resume => {
+						*self = GameState::Playing;
+						None
+					},
+					MenuItem::BackToTitle => Some(StateCommand::LeaveGame),
+					MenuItem::Quit => Some(StateCommand::Quit),
+				},
+				_ => None,
+			},
+		}
+	}
+
+	/// Selects the menu item or the level under the cursor, if there is one.
+	pub fn hover(&mut self, cursor_position: cgmath::Point2<f32>) {
+		match self {
+			GameState::Title(level_select) => {
+				if let Some(index) = level_select.entry_at(cursor_position) {
+					level_select.selected = index;
+				}
+			},
+			GameState::Paused(menu) => {
+				if let Some(index) = menu.item_at(cursor_position) {
+					menu.selected = index;
+				}
+			},
+			_ => {},
+		}
+	}
+
+	/// The texts to render over the game, for the state to show.
+	pub fn texts(&self) -> Vec<RenderedText> {
+		let title = |text: &str, y: f32, height: f32| RenderedText {
+			text: text.to_string(),
+			position: (0.0, y).into(),
+			alignment: TextAlignment::Center,
+			height,
+			color: [1.0, 1.0, 1.0],
+		};
+		match self {
+			GameState::Title(level_select) => {
+				let mut texts = vec![title("Gravinyon", 0.3, 0.08)];
+				texts.extend(level_select.texts());
+				texts.push(RenderedText {
+					text: "Click or press Enter to play".to_string(),
+					position: (0.0, -0.42).into(),
+					alignment: TextAlignment::Center,
+					height: 0.03,
+					color: Menu::COLOR,
+				});
+				texts
+			},
+			GameState::Playing | GameState::GameOver => Vec::new(),
+			GameState::Paused(menu) => {
+				let mut texts = vec![title("Paused", 0.15, 0.07)];
+				texts.extend(menu.texts());
+				texts
+			},
+		}
+	}
+}
@@ -0,0 +1,227 @@
+//! Level definitions, that describe how many obstacles there are, how they move and how they
+//! fight back. They are written in TOML, see the bundled levels in the `levels` directory.
+
+use serde::{Deserialize, Serialize};
+
+/// A range of values, written `[min, max]`, from which random values are taken (`max` excluded).
+pub type RandomRange = [f32; 2];
+
+/// Everything that makes a level different from another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+	pub name: String,
+	#[serde(default)]
+	pub description: String,
+	pub obstacles: ObstacleRules,
+	#[serde(default)]
+	pub enemy_fire: EnemyFireRules,
+}
+
+/// How obstacles spawn. All the speeds are per step.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObstacleRules {
+	/// How many obstacles there are at the beginning of a game.
+	pub initial_count: usize,
+	/// How many obstacles spawn each time one is taken down.
+	pub respawn_count: usize,
+	/// How many hits an obstacle can take before being taken down.
+	pub life: u32,
+	pub scale: RandomRange,
+	/// Obstacles spawn on the right edge, somewhere in this range of heights.
+	pub spawn_y: RandomRange,
+	pub speed_x: RandomRange,
+	pub speed_y: RandomRange,
+	/// In radians per step.
+	pub rotation_speed: RandomRange,
+}
+
+impl ObstacleRules {
+	/// More obstacles than that would slow the game to a crawl (or run out of memory).
+	pub const MAX_INITIAL_COUNT: usize = 200;
+	/// Obstacles multiply quickly enough with a few of them spawning for each one taken down.
+	pub const MAX_RESPAWN_COUNT: usize = 10;
+}
+
+/// Which ways obstacles have to fire enemy shots, none of them being required.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyFireRules {
+	/// Obstacles fire at the ship when it is in range.
+	pub at_ship: Option<FireRule>,
+	/// Obstacles fire at the cursor when it is in range.
+	pub at_cursor: Option<FireRule>,
+	/// Obstacles in range of each other fire away from each other.
+	pub at_each_other: Option<FireRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FireRule {
+	/// How close the target has to be for obstacles to fire at it.
+	pub range: f32,
+	/// Obstacles fire every `interval` steps while the target is in range (1 means every step).
+	pub interval: u32,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+	Io(std::io::Error),
+	Parse(toml::de::Error),
+	/// The level parses fine but some value does not make sense.
+	Invalid(String),
+}
+
+impl std::fmt::Display for LevelError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			LevelError::Io(error) => write!(f, "{error}"),
+			LevelError::Parse(error) => write!(f, "{error}"),
+			LevelError::Invalid(reason) => write!(f, "invalid level: {reason}"),
+		}
+	}
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+	fn from(error: std::io::Error) -> LevelError {
+		LevelError::Io(error)
+	}
+}
+
+impl From<toml::de::Error> for LevelError {
+	fn from(error: toml::de::Error) -> LevelError {
+		LevelError::Parse(error)
+	}
+}
+
+/// The source of the levels that come with the game, in order.
+const BUNDLED_LEVELS: [&str; 4] = [
+	include_str!("../levels/one.toml"),
+	include_str!("../levels/two.toml"),
+	include_str!("../levels/three.toml"),
+	include_str!("../levels/four.toml"),
+];
+
+impl Level {
+	/// The levels that come with the game, in order.
+	pub fn bundled() -> Vec<Level> {
+		BUNDLED_LEVELS
+			.iter()
+			.map(|source| Level::parse(source).expect("bundled levels are valid"))
+			.collect()
+	}
+
+	/// Finds a bundled level by its number (starting at 1) or its name (case insensitive).
+	pub fn find_bundled(number_or_name: &str) -> Option<Level> {
+		let levels = Level::bundled();
+		match number_or_name.parse::<usize>() {
+			Ok(number) => levels.into_iter().nth(number.checked_sub(1)?),
+			Err(_) => levels
+				.into_iter()
+				.find(|level| level.name.eq_ignore_ascii_case(number_or_name)),
+		}
+	}
+
+	pub fn load(path: impl AsRef<std::path::Path>) -> Result<Level, LevelError> {
+		Level::parse(&std::fs::read_to_string(path)?)
+	}
+
+	pub fn parse(source: &str) -> Result<Level, LevelError> {
+		let level: Level = toml::from_str(source)?;
+		level.validate()?;
+		Ok(level)
+	}
+
+	/// What the high scores of the level are kept under: the name for the bundled levels, and for
+	/// other levels the name along with a hash of the level, so that a level file never gets in
+	/// the high scores of another level that has the same name.
+	pub fn high_scores_key(&self) -> String {
+		if Level::bundled().contains(self) {
+			return self.name.clone();
+		}
+		// FNV-1a, that gives the same hash on every platform and with every version of Rust.
+		let hash = self
+			.to_source()
+			.bytes()
+			.fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+				(hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+			});
+		format!("custom:{}:{hash:016x}", self.name)
+	}
+
+	/// The level written back in the same format it is loaded from.
+	pub fn to_source(&self) -> String {
+		toml::to_string(self).unwrap()
+	}
+
+	fn validate(&self) -> Result<(), LevelError> {
+		let obstacles = &self.obstacles;
+		let ranges = [
+			("obstacles.scale", obstacles.scale),
+			("obstacles.spawn_y", obstacles.spawn_y),
+			("obstacles.speed_x", obstacles.speed_x),
+			("obstacles.speed_y", obstacles.speed_y),
+			("obstacles.rotation_speed", obstacles.rotation_speed),
+		];
+		for (name, [min, max]) in ranges {
+			if !(min.is_finite() && max.is_finite() && min < max) {
+				return Err(LevelError::Invalid(format!(
+					"`{name}` must be two finite numbers, the first smaller than the second"
+				)));
+			}
+		}
+		if obstacles.scale[0] <= 0.0 {
+			return Err(LevelError::Invalid(
+				"`obstacles.scale` must be positive".to_string(),
+			));
+		}
+		let counts = [
+			(
+				"obstacles.initial_count",
+				obstacles.initial_count,
+				ObstacleRules::MAX_INITIAL_COUNT,
+			),
+			(
+				"obstacles.respawn_count",
+				obstacles.respawn_count,
+				ObstacleRules::MAX_RESPAWN_COUNT,
+			),
+		];
+		for (name, count, max) in counts {
+			if count > max {
+				return Err(LevelError::Invalid(format!(
+					"`{name}` must be at most {max}"
+				)));
+			}
+		}
+		if obstacles.life == 0 {
+			return Err(LevelError::Invalid(
+				"`obstacles.life` must be at least 1".to_string(),
+			));
+		}
+		let fire_rules = [
+			("enemy_fire.at_ship", self.enemy_fire.at_ship),
+			("enemy_fire.at_cursor", self.enemy_fire.at_cursor),
+			("enemy_fire.at_each_other", self.enemy_fire.at_each_other),
+		];
+		for (name, rule) in fire_rules {
+			let Some(rule) = rule else {
+				continue;
+			};
+			if !(rule.range.is_finite() && rule.range > 0.0) {
+				return Err(LevelError::Invalid(format!(
+					"`{name}.range` must be positive"
+				)));
+			}
+			if rule.interval == 0 {
+				return Err(LevelError::Invalid(format!(
+					"`{name}.interval` must be at least 1"
+				)));
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,98 @@
+use std::{f32::consts::TAU, sync::OnceLock};
+
+use crate::world::Object;
+
+/// The geometry of the object meshes, shared by the rendering and the collisions so that what
+/// collides is exactly what is seen. Each triangle is made of 3 vertex positions, the first
+/// vertex of each triangle being the center of the mesh that is a bit raised.
+pub type Triangle = [[f32; 3]; 3];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WhichMesh {
+	Obstacle,
+	Shot,
+	Ship,
+	EnemyShot,
+}
+
+impl WhichMesh {
+	pub fn all_meshes() -> impl Iterator<Item = WhichMesh> {
+		[
+			WhichMesh::Obstacle,
+			WhichMesh::Shot,
+			WhichMesh::Ship,
+			WhichMesh::EnemyShot,
+		]
+		.into_iter()
+	}
+
+	pub(crate) fn of_object(object: &Object) -> WhichMesh {
+		match object {
+			Object::Obstacle { .. } => WhichMesh::Obstacle,
+			Object::Shot { .. } => WhichMesh::Shot,
+			Object::Ship { .. } => WhichMesh::Ship,
+			Object::EnemyShot { .. } => WhichMesh::EnemyShot,
+		}
+	}
+
+	pub fn triangles(self) -> &'static [Triangle] {
+		match self {
+			WhichMesh::Obstacle => obstacle_triangles(),
+			WhichMesh::Ship => &SHIP_TRIANGLES,
+			WhichMesh::Shot => &SHOT_TRIANGLES,
+			WhichMesh::EnemyShot => &ENEMY_SHOT_TRIANGLES,
+		}
+	}
+
+	/// The distance (in the screen plane) from the center of the mesh to its farthest vertex,
+	/// when the mesh is not scaled.
+	pub fn bounding_radius(self) -> f32 {
+		self
+			.triangles()
+			.iter()
+			.flatten()
+			.map(|[x, y, _z]| f32::hypot(*x, *y))
+			.fold(0.0, f32::max)
+	}
+}
+
+const CENTER: [f32; 3] = [0.0, 0.0, 0.1];
+
+fn obstacle_triangles() -> &'static [Triangle] {
+	static TRIANGLES: OnceLock<Vec<Triangle>> = OnceLock::new();
+	TRIANGLES.get_or_init(|| {
+		let n = 5;
+		(0..n)
+			.map(|i| {
+				let angle_i = i as f32 / n as f32 * TAU;
+				let angle_i_plus_1 = (i + 1) as f32 / n as f32 * TAU;
+				[
+					CENTER,
+					[f32::cos(angle_i), f32::sin(angle_i), 0.0],
+					[f32::cos(angle_i_plus_1), f32::sin(angle_i_plus_1), 0.0],
+				]
+			})
+			.collect()
+	})
+}
+
+const SHIP_TRIANGLES: [Triangle; 4] = [
+	[CENTER, [1.0, -1.0, 0.0], [0.0, 1.0, 0.0]],
+	[CENTER, [0.0, -0.5, 0.0], [1.0, -1.0, 0.0]],
+	[CENTER, [-1.0, -1.0, 0.0], [0.0, -0.5, 0.0]],
+	[CENTER, [0.0, 1.0, 0.0], [-1.0, -1.0, 0.0]],
+];
+
+const SHOT_TRIANGLES: [Triangle; 4] = [
+	[CENTER, [0.6, 0.0, 0.0], [0.0, 1.5, 0.0]],
+	[CENTER, [0.0, -7.0, 0.0], [0.6, 0.0, 0.0]],
+	[CENTER, [-0.6, 0.0, 0.0], [0.0, -7.0, 0.0]],
+	[CENTER, [0.0, 1.5, 0.0], [-0.6, 0.0, 0.0]],
+];
+
+const ENEMY_SHOT_TRIANGLES: [Triangle; 4] = [
+	[CENTER, [0.6, 0.0, 0.0], [0.0, 1.5, 0.0]],
+	[CENTER, [0.0, -3.5, 0.0], [0.6, 0.0, 0.0]],
+	[CENTER, [-0.6, 0.0, 0.0], [0.0, -3.5, 0.0]],
+	[CENTER, [0.0, 1.5, 0.0], [-0.6, 0.0, 0.0]],
+];
@@ -0,0 +1,464 @@
+//! Background music in layers that play together in a loop, more layers being heard as the game
+//! gets more intense, and all of them being ducked while the game is over.
+//!
+//! Each layer is a file in the sounds directory if there is one, else a pattern sequenced with
+//! the synthesizer, so that there is music without any file.
+
+use std::{
+	collections::HashSet,
+	fs::File,
+	io::BufReader,
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use cgmath::MetricSpace;
+use rodio::Source;
+
+use crate::{
+	sound::Sound,
+	synth::{Envelope, Synth, Waveform},
+	world::World,
+};
+
+/// What makes the game intense, as seen by the music.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Intensity {
+	pub obstacles: usize,
+	pub enemy_shots: usize,
+	/// How far the closest obstacle or enemy shot is from the ship, if there is any.
+	pub danger_distance: Option<f32>,
+}
+
+impl Intensity {
+	pub fn of_world(world: &World) -> Intensity {
+		let objects = world.objects();
+		let ship_position = objects[0].position();
+		let dangers = objects
+			.iter()
+			.filter(|object| object.is_obstacle() || object.is_enemy_shot());
+		Intensity {
+			obstacles: objects.iter().filter(|object| object.is_obstacle()).count(),
+			enemy_shots: objects
+				.iter()
+				.filter(|object| object.is_enemy_shot())
+				.count(),
+			danger_distance: dangers
+				.map(|object| object.position().distance(ship_position) - object.scale())
+				.min_by(f32::total_cmp),
+		}
+	}
+
+	/// How intense it is, from 0 (calm) to 1 (as intense as it gets).
+	pub fn level(&self) -> f32 {
+		let obstacles = (self.obstacles as f32 / 12.0).min(1.0);
+		let enemy_shots = (self.enemy_shots as f32 / 8.0).min(1.0);
+		let danger = self
+			.danger_distance
+			.map_or(0.0, |distance| (1.0 - distance / 0.4).clamp(0.0, 1.0));
+		(0.35 * obstacles + 0.3 * enemy_shots + 0.35 * danger).min(1.0)
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MusicLayer {
+	/// Always heard.
+	Base,
+	/// Heard when things get going.
+	Drive,
+	/// Heard when it is about to go wrong.
+	Danger,
+}
+
+impl MusicLayer {
+	pub const ALL: [MusicLayer; 3] = [MusicLayer::Base, MusicLayer::Drive, MusicLayer::Danger];
+
+	/// The file that is played instead of the sequenced track, if it is in the sounds directory.
+	pub fn file_name(self) -> &'static str {
+		match self {
+			MusicLayer::Base => "music_base.ogg",
+			MusicLayer::Drive => "music_drive.ogg",
+			MusicLayer::Danger => "music_danger.ogg",
+		}
+	}
+
+	/// How loud the layer is compared to the others, before the volume settings apply.
+	fn gain(self) -> f32 {
+		match self {
+			MusicLayer::Base => 0.25,
+			MusicLayer::Drive => 0.15,
+			MusicLayer::Danger => 0.15,
+		}
+	}
+
+	/// How loud the layer should be at the given intensity level, from 0 to 1.
+	pub fn target_volume(self, intensity_level: f32) -> f32 {
+		let (start, end) = match self {
+			MusicLayer::Base => return 1.0,
+			MusicLayer::Drive => (0.15, 0.45),
+			MusicLayer::Danger => (0.5, 0.8),
+		};
+		((intensity_level - start) / (end - start)).clamp(0.0, 1.0)
+	}
+
+	fn patterns(self) -> Vec<Pattern> {
+		let pluck = |waveform, decay| Synth {
+			waveform,
+			start_frequency: 0.0,
+			end_frequency: 0.0,
+			noise: 0.0,
+			envelope: Envelope {
+				attack: 0.005,
+				decay,
+				sustain_level: 0.0,
+				sustain: 0.0,
+				release: 0.0,
+			},
+		};
+		match self {
+			MusicLayer::Base => vec![Pattern {
+				notes: &[
+					45, 0, 45, 0, 57, 0, 45, 0, 48, 0, 48, 0, 60, 0, 47, 0, //
+					43, 0, 43, 0, 55, 0, 43, 0, 40, 0, 40, 0, 52, 0, 44, 0,
+				],
+				instrument: pluck(Waveform::Triangle, 0.25),
+				pitch_drop: 1.0,
+			}],
+			MusicLayer::Drive => vec![
+				Pattern {
+					notes: &[
+						69, 72, 76, 72, 69, 72, 76, 79, 72, 76, 79, 76, 72, 76, 79, 84, //
+						67, 71, 74, 71, 67, 71, 74, 79, 64, 68, 71, 68, 64, 68, 71, 76,
+					],
+					instrument: pluck(Waveform::Square, 0.08),
+					pitch_drop: 1.0,
+				},
+				// Hi-hats.
+				Pattern {
+					notes: &[
+						0, 0, 100, 0, 0, 0, 100, 0, 0, 0, 100, 0, 0, 0, 100, 100, //
+						0, 0, 100, 0, 0, 0, 100, 0, 0, 0, 100, 0, 0, 100, 100, 100,
+					],
+					instrument: Synth { noise: 1.0, ..pluck(Waveform::Square, 0.03) },
+					pitch_drop: 1.0,
+				},
+			],
+			MusicLayer::Danger => vec![
+				// Kicks.
+				Pattern {
+					notes: &[
+						40, 0, 0, 0, 40, 0, 0, 0, 40, 0, 0, 0, 40, 0, 40, 0, //
+						40, 0, 0, 0, 40, 0, 0, 0, 40, 0, 0, 0, 40, 0, 40, 40,
+					],
+					instrument: pluck(Waveform::Sine, 0.15),
+					pitch_drop: 0.25,
+				},
+				Pattern {
+					notes: &[
+						57, 57, 58, 57, 57, 57, 58, 57, 57, 57, 58, 57, 57, 58, 57, 58, //
+						55, 55, 56, 55, 55, 55, 56, 55, 52, 52, 53, 52, 56, 56, 57, 58,
+					],
+					instrument: pluck(Waveform::Sawtooth, 0.1),
+					pitch_drop: 1.0,
+				},
+			],
+		}
+	}
+
+	/// The track of the layer sequenced with the synthesizer, that loops seamlessly.
+	pub fn sequenced_track(self) -> Sound {
+		let step_length = (Pattern::STEP_DURATION * Synth::SAMPLE_RATE as f32).round() as usize;
+		let mut samples = vec![0.0; step_length * Pattern::STEPS];
+		for pattern in self.patterns() {
+			pattern.render_into(&mut samples, step_length);
+		}
+		Sound {
+			channels: 1,
+			sample_rate: Synth::SAMPLE_RATE,
+			samples: samples.into(),
+		}
+	}
+}
+
+/// Notes played by an instrument over a loop, one note (or none) per step.
+struct Pattern {
+	/// The notes as MIDI note numbers, 0 being no note.
+	notes: &'static [u8; Pattern::STEPS],
+	/// The sound of a note, the frequencies being replaced by the ones of the note.
+	instrument: Synth,
+	/// The frequency at the end of a note, relative to its frequency.
+	pitch_drop: f32,
+}
+
+impl Pattern {
+	/// Two bars of sixteenth notes.
+	const STEPS: usize = 32;
+	/// A sixteenth note at 112 beats per minute.
+	const STEP_DURATION: f32 = 60.0 / 112.0 / 4.0;
+
+	/// Adds the notes to the samples of the whole loop, the end of the last notes wrapping around
+	/// to the beginning.
+	fn render_into(&self, samples: &mut [f32], step_length: usize) {
+		for (step, &note) in self.notes.iter().enumerate() {
+			if note == 0 {
+				continue;
+			}
+			let frequency = 440.0 * 2.0_f32.powf((note as f32 - 69.0) / 12.0);
+			let synth = Synth {
+				start_frequency: frequency,
+				end_frequency: frequency * self.pitch_drop,
+				..self.instrument
+			};
+			for (index, sample) in synth.source().enumerate() {
+				let index = (step * step_length + index) % samples.len();
+				samples[index] += sample;
+			}
+		}
+	}
+}
+
+/// What a layer plays.
+#[derive(Clone)]
+enum Track {
+	/// Decoded as it plays (music files are too long to be decoded all at once), the file being
+	/// opened again for each loop.
+	File(PathBuf),
+	Sequenced(Sound),
+}
+
+impl Track {
+	/// Checks that the file can be decoded.
+	fn open_file(path: &Path) -> Result<Track, String> {
+		Track::decode_file(path)?;
+		Ok(Track::File(path.to_path_buf()))
+	}
+
+	fn decode_file(path: &Path) -> Result<rodio::Decoder<BufReader<File>>, String> {
+		let file = File::open(path).map_err(|error| error.to_string())?;
+		rodio::Decoder::new(BufReader::new(file)).map_err(|error| error.to_string())
+	}
+
+	/// The track from its beginning, silent if the file can't be decoded anymore.
+	fn source(&self) -> Box<dyn Source<Item = f32> + Send> {
+		match self {
+			Track::File(path) => match Track::decode_file(path) {
+				Ok(decoder) => Box::new(decoder.convert_samples()),
+				Err(error) => {
+					log::warn!(
+						"Failed to play the music \"{}\" again: {error}",
+						path.display()
+					);
+					Box::new(rodio::source::Empty::new())
+				},
+			},
+			Track::Sequenced(sound) => Box::new(sound.source()),
+		}
+	}
+
+	/// Goes through the whole file to know its duration if it does not tell it, without keeping
+	/// the samples.
+	fn duration(&self) -> Duration {
+		let source = self.source();
+		if let Some(duration) = source.total_duration() {
+			return duration;
+		}
+		let (channels, sample_rate) = (source.channels(), source.sample_rate());
+		let frames = source.count() / channels as usize;
+		Duration::from_secs_f64(frames as f64 / sample_rate as f64)
+	}
+}
+
+/// Plays a track over and over, each loop lasting exactly the loop duration of the music (the
+/// track being cut or followed by silence) so that all the layers start their loops together.
+pub struct LoopedTrack {
+	track: Track,
+	/// Where the current loop is in the track.
+	source: Box<dyn Source<Item = f32> + Send>,
+	channels: u16,
+	sample_rate: u32,
+	samples_per_loop: usize,
+	sample_index: usize,
+}
+
+impl LoopedTrack {
+	fn new(track: Track, loop_duration: Duration) -> LoopedTrack {
+		let source = track.source();
+		let (channels, sample_rate) = (source.channels(), source.sample_rate());
+		let frames_per_loop = (loop_duration.as_secs_f64() * sample_rate as f64).round() as usize;
+		LoopedTrack {
+			track,
+			source,
+			channels,
+			sample_rate,
+			samples_per_loop: (frames_per_loop * channels as usize).max(1),
+			sample_index: 0,
+		}
+	}
+}
+
+impl Iterator for LoopedTrack {
+	type Item = f32;
+
+	fn next(&mut self) -> Option<f32> {
+		if self.sample_index == self.samples_per_loop {
+			self.sample_index = 0;
+			self.source = self.track.source();
+		}
+		self.sample_index += 1;
+		Some(self.source.next().unwrap_or(0.0))
+	}
+}
+
+impl Source for LoopedTrack {
+	fn current_frame_len(&self) -> Option<usize> {
+		Some(self.samples_per_loop - self.sample_index)
+	}
+
+	fn channels(&self) -> u16 {
+		self.channels
+	}
+
+	fn sample_rate(&self) -> u32 {
+		self.sample_rate
+	}
+
+	fn total_duration(&self) -> Option<Duration> {
+		None
+	}
+}
+
+/// The layers playing, each at its own volume that fades towards what the intensity asks for.
+pub struct Music {
+	tracks: Vec<(MusicLayer, Track)>,
+	/// The duration of the base layer, that all the layers loop on.
+	loop_duration: Duration,
+	sequenced: HashSet<MusicLayer>,
+	/// Only there once the music plays on an audio device.
+	sinks: Vec<(MusicLayer, rodio::Sink)>,
+	layer_volumes: Vec<(MusicLayer, f32)>,
+	/// How much of the volume is left by the ducking, from 0 to 1.
+	ducking: f32,
+}
+
+impl Music {
+	/// How much the volume of a layer changes per second, during a crossfade.
+	const FADE_SPEED: f32 = 0.5;
+	/// How much of the volume is left while the game is over.
+	const DUCKED_VOLUME: f32 = 0.3;
+	const DUCKING_SPEED: f32 = 2.0;
+
+	/// Loads the layers from the given directory, the missing ones being sequenced. The files
+	/// should all last as long as the base layer (be it a file or not), the other layers are cut or
+	/// followed by silence to loop with it.
+	pub fn load(directory: &Path) -> Music {
+		let mut sequenced = HashSet::new();
+		let tracks = MusicLayer::ALL
+			.into_iter()
+			.map(|layer| {
+				let path = directory.join(layer.file_name());
+				let track = if path.exists() {
+					Track::open_file(&path)
+						.map_err(|error| {
+							log::warn!(
+								"Failed to load the music \"{}\" ({error}), a sequenced track will be played instead",
+								path.display()
+							)
+						})
+						.ok()
+				} else {
+					None
+				};
+				let track = track.unwrap_or_else(|| {
+					sequenced.insert(layer);
+					Track::Sequenced(layer.sequenced_track())
+				});
+				(layer, track)
+			})
+			.collect::<Vec<_>>();
+		let loop_duration = match tracks[0].1.duration() {
+			Duration::ZERO => {
+				log::warn!("The base layer of the music is empty, the sequenced one sets the loop");
+				Track::Sequenced(MusicLayer::Base.sequenced_track()).duration()
+			},
+			duration => duration,
+		};
+		Music {
+			tracks,
+			loop_duration,
+			sequenced,
+			sinks: Vec::new(),
+			layer_volumes: MusicLayer::ALL.map(|layer| (layer, 0.0)).to_vec(),
+			ducking: 1.0,
+		}
+	}
+
+	/// Starts all the layers at the same time (silent until the next update), looping forever.
+	pub fn start(&mut self, stream_handle: &rodio::OutputStreamHandle) {
+		for layer in MusicLayer::ALL {
+			match rodio::Sink::try_new(stream_handle) {
+				Ok(sink) => {
+					sink.set_volume(0.0);
+					sink.append(self.layer_source(layer));
+					self.sinks.push((layer, sink));
+				},
+				Err(error) => log::warn!("Failed to play the music: {error}"),
+			}
+		}
+	}
+
+	/// What the layer plays, looping forever.
+	pub fn layer_source(&self, layer: MusicLayer) -> LoopedTrack {
+		let (_, track) = self
+			.tracks
+			.iter()
+			.find(|(some_layer, _)| *some_layer == layer)
+			.unwrap();
+		LoopedTrack::new(track.clone(), self.loop_duration)
+	}
+
+	/// How long a loop of the music lasts, all the layers looping together.
+	pub fn loop_duration(&self) -> Duration {
+		self.loop_duration
+	}
+
+	/// Is the layer played with its sequenced track, rather than with a file.
+	pub fn is_sequenced(&self, layer: MusicLayer) -> bool {
+		self.sequenced.contains(&layer)
+	}
+
+	/// How loud the layer is in the crossfade, from 0 to 1.
+	pub fn layer_volume(&self, layer: MusicLayer) -> f32 {
+		self
+			.layer_volumes
+			.iter()
+			.find(|(some_layer, _)| *some_layer == layer)
+			.map_or(0.0, |(_, volume)| *volume)
+	}
+
+	/// How much of the volume is left by the ducking, from 0 to 1.
+	pub fn ducking(&self) -> f32 {
+		self.ducking
+	}
+
+	/// Moves the crossfade and the ducking towards what the intensity level and the game over ask
+	/// for, by the time elapsed since the last update. The volume is the one of the music settings.
+	pub fn update(&mut self, intensity_level: f32, game_over: bool, volume: f32, elapsed: Duration) {
+		let elapsed = elapsed.as_secs_f32();
+		let approach = |value: &mut f32, target: f32, speed: f32| {
+			*value += (target - *value).clamp(-speed * elapsed, speed * elapsed);
+		};
+		for (layer, layer_volume) in self.layer_volumes.iter_mut() {
+			approach(
+				layer_volume,
+				layer.target_volume(intensity_level),
+				Music::FADE_SPEED,
+			);
+		}
+		let ducking = if game_over { Music::DUCKED_VOLUME } else { 1.0 };
+		approach(&mut self.ducking, ducking, Music::DUCKING_SPEED);
+
+		for (layer, sink) in &self.sinks {
+			sink.set_volume(layer.gain() * self.layer_volume(*layer) * self.ducking * volume);
+		}
+	}
+}
@@ -0,0 +1,1219 @@
+use std::{collections::HashMap, f32::consts::TAU};
+
+use cgmath::{InnerSpace, MetricSpace};
+use wgpu::util::DeviceExt;
+
+use crate::{
+	font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH},
+	mesh::WhichMesh,
+	world::{Object, ObjectId, World},
+};
+
+/// Vertex type used for mesh in object shader.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct ObjectVertexPod {
+	position: [f32; 3],
+	color: [f32; 3],
+	normal: [f32; 3],
+}
+
+/// Vertex type used for mesh in shape shader.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct ShapeVertexPod {
+	position: [f32; 3],
+	color: [f32; 3],
+}
+
+/// Instance type used with object shader.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct ObjectInstancePod {
+	position: [f32; 2],
+	angle: f32,
+	scale: f32,
+	shade_sensitivity: f32,
+}
+
+/// Vector in 3D.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vector3Pod {
+	values: [f32; 3],
+}
+
+/// Vector in 2D.
+#[derive(Copy, Clone, Debug)]
+/// Certified Plain Old Data (so it can be sent to the GPU as a uniform).
+#[repr(C)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vector2Pod {
+	values: [f32; 2],
+}
+
+fn make_z_buffer_texture_view(
+	device: &wgpu::Device,
+	format: wgpu::TextureFormat,
+	width: u32,
+	height: u32,
+) -> wgpu::TextureView {
+	let z_buffer_texture_description = wgpu::TextureDescriptor {
+		label: Some("Z Buffer"),
+		size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+		mip_level_count: 1,
+		sample_count: 1,
+		dimension: wgpu::TextureDimension::D2,
+		format,
+		view_formats: &[],
+		usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+	};
+	let z_buffer_texture = device.create_texture(&z_buffer_texture_description);
+	z_buffer_texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+struct UniformStuff {
+	binding: u32,
+	buffer: wgpu::Buffer,
+	bind_group_layout_entry: wgpu::BindGroupLayoutEntry,
+}
+
+impl UniformStuff {
+	fn new(
+		device: &wgpu::Device,
+		name: &str,
+		binding: u32,
+		usage: wgpu::BufferUsages,
+		visibility: wgpu::ShaderStages,
+		contents: &[u8],
+	) -> UniformStuff {
+		let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some(&format!("{name} Buffer")),
+			contents,
+			usage,
+		});
+		let bind_group_layout_entry = wgpu::BindGroupLayoutEntry {
+			binding,
+			visibility,
+			ty: wgpu::BindingType::Buffer {
+				ty: wgpu::BufferBindingType::Uniform,
+				has_dynamic_offset: false,
+				min_binding_size: None,
+			},
+			count: None,
+		};
+		UniformStuff { binding, buffer, bind_group_layout_entry }
+	}
+
+	fn bind_group_entry(&self) -> wgpu::BindGroupEntry<'_> {
+		wgpu::BindGroupEntry { binding: self.binding, resource: self.buffer.as_entire_binding() }
+	}
+}
+
+/// Where and how an object is rendered, without the rest of its state.
+#[derive(Clone, Copy)]
+pub(crate) struct ObjectPose {
+	position: cgmath::Point2<f32>,
+	mesh_angle: f32,
+}
+
+impl ObjectPose {
+	fn of_object(object: &Object, cursor_position: cgmath::Point2<f32>) -> ObjectPose {
+		let position = object.position();
+		let mesh_angle = object.mesh_angle(cursor_position);
+		ObjectPose { position, mesh_angle }
+	}
+
+	fn interpolate(&self, next: &ObjectPose, ratio: f32) -> ObjectPose {
+		// An object that wrapped around the screen edges has not actually crossed the screen.
+		if self.position.distance(next.position) > 0.5 {
+			return *next;
+		}
+		let position = self.position + (next.position - self.position) * ratio;
+		// The shortest way around, so that an angle going from just below `TAU` to just above 0
+		// does not make the object spin a whole turn backwards.
+		let angle_difference =
+			(next.mesh_angle - self.mesh_angle + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0;
+		let mesh_angle = self.mesh_angle + angle_difference * ratio;
+		ObjectPose { position, mesh_angle }
+	}
+}
+
+pub(crate) fn world_poses(world: &World) -> HashMap<ObjectId, ObjectPose> {
+	world
+		.objects()
+		.iter()
+		.map(|object| {
+			(
+				object.id(),
+				ObjectPose::of_object(object, world.cursor_position()),
+			)
+		})
+		.collect()
+}
+
+/// One mesh to render somewhere, independently of the game objects, so that anything can be
+/// rendered (not only what is in a `World`).
+#[derive(Clone, Copy, Debug)]
+pub struct RenderedObject {
+	pub mesh: WhichMesh,
+	pub position: cgmath::Point2<f32>,
+	pub angle: f32,
+	pub scale: f32,
+	/// How much the lighting affects the colors, 0.0 meaning not at all.
+	pub shade_sensitivity: f32,
+}
+
+impl RenderedObject {
+	/// What should be rendered of the given world, its objects being placed in between the
+	/// poses they had before the previous step and their current poses.
+	pub(crate) fn of_world(
+		world: &World,
+		previous_poses: &HashMap<ObjectId, ObjectPose>,
+		interpolation: f32,
+	) -> Vec<RenderedObject> {
+		let mut rendered_objects = Vec::new();
+		for object in world.objects().iter() {
+			if object.is_ship() && world.is_game_over() {
+				continue;
+			}
+
+			let pose = ObjectPose::of_object(object, world.cursor_position());
+			let pose = match previous_poses.get(&object.id()) {
+				Some(previous_pose) => previous_pose.interpolate(&pose, interpolation),
+				None => pose,
+			};
+			let shade_sensitivity = match object {
+				Object::Obstacle { .. } | Object::Ship { .. } => 3.0,
+				Object::Shot { .. } | Object::EnemyShot { .. } => 0.0,
+			};
+
+			rendered_objects.push(RenderedObject {
+				mesh: WhichMesh::of_object(object),
+				position: pose.position,
+				angle: pose.mesh_angle,
+				scale: object.scale(),
+				shade_sensitivity,
+			});
+		}
+		rendered_objects
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlignment {
+	Left,
+	Center,
+	Right,
+}
+
+/// A line of text to render over everything else, in the same coordinates as the objects.
+#[derive(Clone, Debug)]
+pub struct RenderedText {
+	pub text: String,
+	/// Where the bottom of the line is, and its left end, center or right end (see `alignment`).
+	pub position: cgmath::Point2<f32>,
+	pub alignment: TextAlignment,
+	/// Height of the characters.
+	pub height: f32,
+	pub color: [f32; 3],
+}
+
+impl RenderedText {
+	const HUD_HEIGHT: f32 = 0.03;
+	/// Distance between the playfield and the HUD text in the black bands.
+	const HUD_MARGIN: f32 = 0.012;
+	const HUD_COLOR: [f32; 3] = [0.8, 0.8, 0.9];
+	const HUD_ALERT_COLOR: [f32; 3] = [1.0, 0.4, 0.5];
+
+	/// The head-up display of the given world, in the black bands above and below the playfield.
+	pub(crate) fn hud_of_world(world: &World, best_score: Option<u32>) -> Vec<RenderedText> {
+		let top_line_y = 0.5 + RenderedText::HUD_MARGIN;
+		let hud_text = |text: String, x: f32, y: f32, alignment: TextAlignment| RenderedText {
+			text,
+			position: (x, y).into(),
+			alignment,
+			height: RenderedText::HUD_HEIGHT,
+			color: RenderedText::HUD_COLOR,
+		};
+		let best_score = best_score.unwrap_or(0).max(world.score());
+		let mut texts = vec![
+			hud_text(
+				format!("Score {}", world.score()),
+				-0.98,
+				top_line_y,
+				TextAlignment::Left,
+			),
+			hud_text(
+				format!("Level {}", world.level().name),
+				0.0,
+				top_line_y,
+				TextAlignment::Center,
+			),
+			hud_text(
+				format!("Best {best_score}"),
+				0.98,
+				top_line_y,
+				TextAlignment::Right,
+			),
+		];
+		if world.is_game_over() {
+			let bottom_line_y = -0.5 - RenderedText::HUD_MARGIN - RenderedText::HUD_HEIGHT;
+			texts.push(RenderedText {
+				color: RenderedText::HUD_ALERT_COLOR,
+				..hud_text(
+					"Game over — click to restart, Escape for the levels".to_string(),
+					0.0,
+					bottom_line_y,
+					TextAlignment::Center,
+				)
+			});
+		}
+		texts
+	}
+
+	/// The triangles of the pixels of the glyphs, to be rendered with the shape pipeline.
+	fn triangles(&self) -> Vec<ShapeVertexPod> {
+		// Each glyph is followed by one pixel of spacing.
+		let pixel_size = self.height / GLYPH_HEIGHT as f32;
+		let advance = (GLYPH_WIDTH + 1) as f32 * pixel_size;
+		let width = self.text.chars().count() as f32 * advance - pixel_size;
+		let left = match self.alignment {
+			TextAlignment::Left => self.position.x,
+			TextAlignment::Center => self.position.x - width / 2.0,
+			TextAlignment::Right => self.position.x - width,
+		};
+		let top = self.position.y + self.height;
+		let color = self.color;
+		let mut vertices = Vec::new();
+		for (character_index, character) in self.text.chars().enumerate() {
+			let Some(rows) = glyph(character) else {
+				continue;
+			};
+			let glyph_left = left + character_index as f32 * advance;
+			for (row_index, row) in rows.into_iter().enumerate() {
+				for column_index in 0..GLYPH_WIDTH {
+					if row & (1 << (GLYPH_WIDTH - 1 - column_index)) == 0 {
+						continue;
+					}
+					let x0 = glyph_left + column_index as f32 * pixel_size;
+					let x1 = x0 + pixel_size;
+					let y1 = top - row_index as f32 * pixel_size;
+					let y0 = y1 - pixel_size;
+					vertices.extend_from_slice(&[
+						ShapeVertexPod { position: [x0, y1, 0.0], color },
+						ShapeVertexPod { position: [x1, y1, 0.0], color },
+						ShapeVertexPod { position: [x0, y0, 0.0], color },
+						ShapeVertexPod { position: [x0, y0, 0.0], color },
+						ShapeVertexPod { position: [x1, y1, 0.0], color },
+						ShapeVertexPod { position: [x1, y0, 0.0], color },
+					]);
+				}
+			}
+		}
+		vertices
+	}
+}
+
+/// The instances of one mesh, and the buffer they are uploaded to. The buffer is kept from one
+/// frame to the next, and is only replaced by a bigger one when the instances don't fit anymore.
+struct InstanceArrayForOneMesh {
+	instances: Vec<ObjectInstancePod>,
+	wgpu_buffer: Option<wgpu::Buffer>,
+	/// How many instances fit in the buffer.
+	capacity: usize,
+}
+
+impl InstanceArrayForOneMesh {
+	/// The smallest buffer is made for that many instances, to avoid growing a few times in a row
+	/// at the start of a game.
+	const MIN_CAPACITY: usize = 64;
+
+	/// Uploads the instances to the buffer, making it bigger first if needed.
+	fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mesh: WhichMesh) {
+		let len = self.instances.len();
+		if len > self.capacity || self.wgpu_buffer.is_none() {
+			self.capacity = len
+				.next_power_of_two()
+				.max(InstanceArrayForOneMesh::MIN_CAPACITY);
+			self.wgpu_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+				label: Some(&format!("{mesh:?} Instance Buffer")),
+				size: (self.capacity * std::mem::size_of::<ObjectInstancePod>()) as wgpu::BufferAddress,
+				usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+				mapped_at_creation: false,
+			}));
+		}
+		if len > 0 {
+			queue.write_buffer(
+				self.wgpu_buffer.as_ref().unwrap(),
+				0,
+				bytemuck::cast_slice(&self.instances),
+			);
+		}
+	}
+
+	/// The part of the buffer that holds the instances of this frame.
+	fn buffer_slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+		let len = std::mem::size_of_val(self.instances.as_slice()) as wgpu::BufferAddress;
+		Some(self.wgpu_buffer.as_ref()?.slice(..len))
+	}
+}
+
+/// The instances to render for each mesh. It is filled again for each frame, so that there are
+/// never holes in the instance arrays (the instances of the objects that are gone are not there
+/// anymore and the ones that remain are packed at the start of the buffers).
+struct InstanceTable {
+	table: HashMap<WhichMesh, InstanceArrayForOneMesh>,
+}
+
+impl InstanceTable {
+	fn new() -> InstanceTable {
+		let mut instance_table = InstanceTable { table: HashMap::new() };
+		for mesh in WhichMesh::all_meshes() {
+			instance_table.table.insert(
+				mesh,
+				InstanceArrayForOneMesh { instances: Vec::new(), wgpu_buffer: None, capacity: 0 },
+			);
+		}
+		instance_table
+	}
+
+	fn instance_array_len(&self, mesh: WhichMesh) -> Option<usize> {
+		Some(self.table.get(&mesh)?.instances.len())
+	}
+
+	fn instance_array_buffer_slice(&self, mesh: WhichMesh) -> Option<wgpu::BufferSlice<'_>> {
+		self.table.get(&mesh)?.buffer_slice()
+	}
+
+	fn insert_new_instance(&mut self, mesh: WhichMesh, instance: ObjectInstancePod) {
+		if let Some(array) = self.table.get_mut(&mesh) {
+			array.instances.push(instance);
+		} else {
+			panic!("The table for mesh {mesh:?} is missing");
+		}
+	}
+
+	fn clear(&mut self) {
+		for array in self.table.values_mut() {
+			array.instances.clear();
+		}
+	}
+}
+
+/// The triangles of the texts of a frame, and the buffer they are uploaded to. Like the instance
+/// buffers, the buffer is kept from one frame to the next and only replaced by a bigger one when
+/// the vertices don't fit anymore.
+struct TextVertexArray {
+	vertices: Vec<ShapeVertexPod>,
+	wgpu_buffer: Option<wgpu::Buffer>,
+	/// How many vertices fit in the buffer.
+	capacity: usize,
+}
+
+impl TextVertexArray {
+	/// About enough for the HUD and a few lines of menu.
+	const MIN_CAPACITY: usize = 4096;
+
+	fn new() -> TextVertexArray {
+		TextVertexArray { vertices: Vec::new(), wgpu_buffer: None, capacity: 0 }
+	}
+
+	/// Uploads the triangles of the given texts to the buffer, making it bigger first if needed.
+	fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, texts: &[RenderedText]) {
+		self.vertices.clear();
+		self
+			.vertices
+			.extend(texts.iter().flat_map(RenderedText::triangles));
+		let len = self.vertices.len();
+		if len > self.capacity || self.wgpu_buffer.is_none() {
+			self.capacity = len.next_power_of_two().max(TextVertexArray::MIN_CAPACITY);
+			self.wgpu_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+				label: Some("Text Vertex Buffer"),
+				size: (self.capacity * std::mem::size_of::<ShapeVertexPod>()) as wgpu::BufferAddress,
+				usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+				mapped_at_creation: false,
+			}));
+		}
+		if len > 0 {
+			queue.write_buffer(
+				self.wgpu_buffer.as_ref().unwrap(),
+				0,
+				bytemuck::cast_slice(&self.vertices),
+			);
+		}
+	}
+
+	/// The part of the buffer that holds the vertices of this frame, if there are any.
+	fn buffer_slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+		if self.vertices.is_empty() {
+			return None;
+		}
+		let len = std::mem::size_of_val(self.vertices.as_slice()) as wgpu::BufferAddress;
+		Some(self.wgpu_buffer.as_ref()?.slice(..len))
+	}
+}
+
+/// Everything needed to render the game into some texture, be it the window or not.
+pub struct Renderer {
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	target_format: wgpu::TextureFormat,
+	width: u32,
+	height: u32,
+	z_buffer_format: wgpu::TextureFormat,
+	z_buffer_view: wgpu::TextureView,
+	object_shader_uniform_aspect_ratio: UniformStuff,
+	object_shader_bind_group: wgpu::BindGroup,
+	object_render_pipeline: wgpu::RenderPipeline,
+	shape_shader_uniform_aspect_ratio: UniformStuff,
+	shape_shader_bind_group: wgpu::BindGroup,
+	shape_render_pipeline: wgpu::RenderPipeline,
+	obstacle_mesh: Vec<ObjectVertexPod>,
+	obstacle_vertex_buffer: wgpu::Buffer,
+	ship_mesh: Vec<ObjectVertexPod>,
+	ship_vertex_buffer: wgpu::Buffer,
+	shot_mesh: Vec<ObjectVertexPod>,
+	shot_vertex_buffer: wgpu::Buffer,
+	enemy_shot_mesh: Vec<ObjectVertexPod>,
+	enemy_shot_vertex_buffer: wgpu::Buffer,
+	top_black_rectangle_mesh: Vec<ShapeVertexPod>,
+	top_black_rectangle_vertex_buffer: wgpu::Buffer,
+	bottom_black_rectangle_mesh: Vec<ShapeVertexPod>,
+	bottom_black_rectangle_vertex_buffer: wgpu::Buffer,
+	instance_table: InstanceTable,
+	text_vertex_array: TextVertexArray,
+}
+
+impl Renderer {
+	const CLEAR_COLOR: wgpu::Color = wgpu::Color { r: 0.02, g: 0.0, b: 0.05, a: 1.0 };
+
+	/// The renderer will render into textures of the given format and size.
+	pub fn new(
+		device: wgpu::Device,
+		queue: wgpu::Queue,
+		target_format: wgpu::TextureFormat,
+		width: u32,
+		height: u32,
+	) -> Renderer {
+		let z_buffer_format = wgpu::TextureFormat::Depth32Float;
+		let z_buffer_view = make_z_buffer_texture_view(&device, z_buffer_format, width, height);
+
+		let aspect_ratio = width as f32 / height as f32;
+
+		let object_shader_uniform_light_direction = UniformStuff::new(
+			&device,
+			"Light Direction",
+			0,
+			wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			wgpu::ShaderStages::VERTEX,
+			bytemuck::cast_slice(&[Vector3Pod { values: [-1.0, 0.0, 0.0] }]),
+		);
+		let object_shader_uniform_aspect_ratio = UniformStuff::new(
+			&device,
+			"Aspect Ratio",
+			1,
+			wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			wgpu::ShaderStages::VERTEX,
+			bytemuck::cast_slice(&[aspect_ratio]),
+		);
+
+		let object_shader_bind_group_layout =
+			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+				entries: &[
+					object_shader_uniform_light_direction.bind_group_layout_entry,
+					object_shader_uniform_aspect_ratio.bind_group_layout_entry,
+				],
+				label: Some("Object Bind Group Layout"),
+			});
+		let object_shader_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			layout: &object_shader_bind_group_layout,
+			entries: &[
+				object_shader_uniform_light_direction.bind_group_entry(),
+				object_shader_uniform_aspect_ratio.bind_group_entry(),
+			],
+			label: Some("Object Bind Group"),
+		});
+
+		let object_render_pipeline = {
+			let object_vertex_buffer_layout = wgpu::VertexBufferLayout {
+				array_stride: std::mem::size_of::<ObjectVertexPod>() as wgpu::BufferAddress,
+				step_mode: wgpu::VertexStepMode::Vertex,
+				attributes: &[
+					wgpu::VertexAttribute {
+						offset: 0,
+						shader_location: 0,
+						format: wgpu::VertexFormat::Float32x3,
+					},
+					wgpu::VertexAttribute {
+						offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+						shader_location: 1,
+						format: wgpu::VertexFormat::Float32x3,
+					},
+					wgpu::VertexAttribute {
+						offset: (std::mem::size_of::<[f32; 3]>() * 2) as wgpu::BufferAddress,
+						shader_location: 2,
+						format: wgpu::VertexFormat::Float32x3,
+					},
+				],
+			};
+			let object_instance_buffer_layout = wgpu::VertexBufferLayout {
+				array_stride: std::mem::size_of::<ObjectInstancePod>() as wgpu::BufferAddress,
+				step_mode: wgpu::VertexStepMode::Instance,
+				attributes: &[
+					wgpu::VertexAttribute {
+						offset: 0,
+						shader_location: 3,
+						format: wgpu::VertexFormat::Float32x2,
+					},
+					wgpu::VertexAttribute {
+						offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+						shader_location: 4,
+						format: wgpu::VertexFormat::Float32,
+					},
+					wgpu::VertexAttribute {
+						offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+						shader_location: 5,
+						format: wgpu::VertexFormat::Float32,
+					},
+					wgpu::VertexAttribute {
+						offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+						shader_location: 6,
+						format: wgpu::VertexFormat::Float32,
+					},
+				],
+			};
+			let object_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+				label: Some("Object Shader"),
+				source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/object.wgsl").into()),
+			});
+			let object_render_pipeline_layout =
+				device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+					label: Some("Object Render Pipeline Layout"),
+					bind_group_layouts: &[&object_shader_bind_group_layout],
+					push_constant_ranges: &[],
+				});
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some("Object Render Pipeline"),
+				layout: Some(&object_render_pipeline_layout),
+				vertex: wgpu::VertexState {
+					module: &object_shader,
+					entry_point: "vertex_shader_main",
+					buffers: &[object_vertex_buffer_layout, object_instance_buffer_layout],
+				},
+				fragment: Some(wgpu::FragmentState {
+					module: &object_shader,
+					entry_point: "fragment_shader_main",
+					targets: &[Some(wgpu::ColorTargetState {
+						format: target_format,
+						blend: Some(wgpu::BlendState::REPLACE),
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				primitive: wgpu::PrimitiveState {
+					topology: wgpu::PrimitiveTopology::TriangleList,
+					strip_index_format: None,
+					front_face: wgpu::FrontFace::Ccw,
+					cull_mode: Some(wgpu::Face::Back),
+					polygon_mode: wgpu::PolygonMode::Fill,
+					unclipped_depth: false,
+					conservative: false,
+				},
+				depth_stencil: Some(wgpu::DepthStencilState {
+					format: z_buffer_format,
+					depth_write_enabled: true,
+					depth_compare: wgpu::CompareFunction::Less,
+					stencil: wgpu::StencilState::default(),
+					bias: wgpu::DepthBiasState::default(),
+				}),
+				multisample: wgpu::MultisampleState {
+					count: 1,
+					mask: !0,
+					alpha_to_coverage_enabled: false,
+				},
+				multiview: None,
+			})
+		};
+
+		let shape_shader_uniform_aspect_ratio = UniformStuff::new(
+			&device,
+			"Aspect Ratio",
+			0,
+			wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			wgpu::ShaderStages::VERTEX,
+			bytemuck::cast_slice(&[aspect_ratio]),
+		);
+
+		let shape_shader_bind_group_layout =
+			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+				entries: &[shape_shader_uniform_aspect_ratio.bind_group_layout_entry],
+				label: Some("Shape Bind Group Layout"),
+			});
+		let shape_shader_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			layout: &shape_shader_bind_group_layout,
+			entries: &[shape_shader_uniform_aspect_ratio.bind_group_entry()],
+			label: Some("Shape Bind Group"),
+		});
+
+		let shape_render_pipeline = {
+			let shape_vertex_buffer_layout = wgpu::VertexBufferLayout {
+				array_stride: std::mem::size_of::<ShapeVertexPod>() as wgpu::BufferAddress,
+				step_mode: wgpu::VertexStepMode::Vertex,
+				attributes: &[
+					wgpu::VertexAttribute {
+						offset: 0,
+						shader_location: 0,
+						format: wgpu::VertexFormat::Float32x3,
+					},
+					wgpu::VertexAttribute {
+						offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+						shader_location: 1,
+						format: wgpu::VertexFormat::Float32x3,
+					},
+				],
+			};
+			let shape_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+				label: Some("Shape Shader"),
+				source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/shape.wgsl").into()),
+			});
+			let shape_render_pipeline_layout =
+				device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+					label: Some("Shape Render Pipeline Layout"),
+					bind_group_layouts: &[&shape_shader_bind_group_layout],
+					push_constant_ranges: &[],
+				});
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some("Shape Render Pipeline"),
+				layout: Some(&shape_render_pipeline_layout),
+				vertex: wgpu::VertexState {
+					module: &shape_shader,
+					entry_point: "vertex_shader_main",
+					buffers: &[shape_vertex_buffer_layout],
+				},
+				fragment: Some(wgpu::FragmentState {
+					module: &shape_shader,
+					entry_point: "fragment_shader_main",
+					targets: &[Some(wgpu::ColorTargetState {
+						format: target_format,
+						blend: Some(wgpu::BlendState::REPLACE),
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				primitive: wgpu::PrimitiveState {
+					topology: wgpu::PrimitiveTopology::TriangleList,
+					strip_index_format: None,
+					front_face: wgpu::FrontFace::Ccw,
+					cull_mode: None, //Some(wgpu::Face::Back),
+					polygon_mode: wgpu::PolygonMode::Fill,
+					unclipped_depth: false,
+					conservative: false,
+				},
+				depth_stencil: Some(wgpu::DepthStencilState {
+					format: z_buffer_format,
+					depth_write_enabled: true,
+					depth_compare: wgpu::CompareFunction::Less,
+					stencil: wgpu::StencilState::default(),
+					bias: wgpu::DepthBiasState::default(),
+				}),
+				multisample: wgpu::MultisampleState {
+					count: 1,
+					mask: !0,
+					alpha_to_coverage_enabled: false,
+				},
+				multiview: None,
+			})
+		};
+		let mut obstacle_mesh = Vec::new();
+		let mut add_triangle = |positions: [[f32; 3]; 3]| {
+			let a: cgmath::Vector3<f32> = positions[0].into();
+			let b: cgmath::Vector3<f32> = positions[1].into();
+			let c: cgmath::Vector3<f32> = positions[2].into();
+			let normal = (a - b).cross(c - b).normalize();
+			let normal: [f32; 3] = normal.into();
+			let color = [0.3, 0.3, 0.3];
+			obstacle_mesh.push(ObjectVertexPod { position: positions[0], color, normal });
+			obstacle_mesh.push(ObjectVertexPod { position: positions[1], color, normal });
+			obstacle_mesh.push(ObjectVertexPod { position: positions[2], color, normal });
+		};
+		for triangle in WhichMesh::Obstacle.triangles() {
+			add_triangle(*triangle);
+		}
+		let obstacle_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Obstacle Vertex Buffer"),
+			contents: bytemuck::cast_slice(&obstacle_mesh),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+
+		let mut ship_mesh = Vec::new();
+		let mut add_triangle = |positions: [[f32; 3]; 3]| {
+			let a: cgmath::Vector3<f32> = positions[0].into();
+			let b: cgmath::Vector3<f32> = positions[1].into();
+			let c: cgmath::Vector3<f32> = positions[2].into();
+			let normal = (a - b).cross(c - b).normalize();
+			let normal: [f32; 3] = normal.into();
+			let color = [0.5, 0.2, 0.5];
+			ship_mesh.push(ObjectVertexPod { position: positions[0], color, normal });
+			ship_mesh.push(ObjectVertexPod { position: positions[1], color, normal });
+			ship_mesh.push(ObjectVertexPod { position: positions[2], color, normal });
+		};
+		for triangle in WhichMesh::Ship.triangles() {
+			add_triangle(*triangle);
+		}
+		let ship_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Ship Vertex Buffer"),
+			contents: bytemuck::cast_slice(&ship_mesh),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+
+		let mut shot_mesh = Vec::new();
+		let mut add_triangle = |positions: [[f32; 3]; 3]| {
+			let a: cgmath::Vector3<f32> = positions[0].into();
+			let b: cgmath::Vector3<f32> = positions[1].into();
+			let c: cgmath::Vector3<f32> = positions[2].into();
+			let normal = (a - b).cross(c - b).normalize();
+			let normal: [f32; 3] = normal.into();
+			let color = [1.0, 0.2, 0.0]; // The center has a different color.
+			shot_mesh.push(ObjectVertexPod { position: positions[0], color, normal });
+			let color = [1.0, 0.0, 0.0];
+			shot_mesh.push(ObjectVertexPod { position: positions[1], color, normal });
+			shot_mesh.push(ObjectVertexPod { position: positions[2], color, normal });
+		};
+		for triangle in WhichMesh::Shot.triangles() {
+			add_triangle(*triangle);
+		}
+		let shot_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Shot Vertex Buffer"),
+			contents: bytemuck::cast_slice(&shot_mesh),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+
+		let mut enemy_shot_mesh = Vec::new();
+		let mut add_triangle = |positions: [[f32; 3]; 3]| {
+			let a: cgmath::Vector3<f32> = positions[0].into();
+			let b: cgmath::Vector3<f32> = positions[1].into();
+			let c: cgmath::Vector3<f32> = positions[2].into();
+			let normal = (a - b).cross(c - b).normalize();
+			let normal: [f32; 3] = normal.into();
+			let color = [0.0, 0.8, 1.0]; // The center has a different color.
+			enemy_shot_mesh.push(ObjectVertexPod { position: positions[0], color, normal });
+			let color = [0.0, 0.5, 1.0];
+			enemy_shot_mesh.push(ObjectVertexPod { position: positions[1], color, normal });
+			enemy_shot_mesh.push(ObjectVertexPod { position: positions[2], color, normal });
+		};
+		for triangle in WhichMesh::EnemyShot.triangles() {
+			add_triangle(*triangle);
+		}
+		let enemy_shot_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("Enemy Shot Vertex Buffer"),
+			contents: bytemuck::cast_slice(&enemy_shot_mesh),
+			usage: wgpu::BufferUsages::VERTEX,
+		});
+
+		let color = [0.0, 0.0, 0.0];
+		let top_black_rectangle_mesh = vec![
+			ShapeVertexPod { position: [-1.0, 1.0, 0.0], color },
+			ShapeVertexPod { position: [1.0, 1.0, 0.0], color },
+			ShapeVertexPod { position: [-1.0, 0.5, 0.0], color },
+			ShapeVertexPod { position: [-1.0, 0.5, 0.0], color },
+			ShapeVertexPod { position: [1.0, 1.0, 0.0], color },
+			ShapeVertexPod { position: [1.0, 0.5, 0.0], color },
+		];
+		let top_black_rectangle_vertex_buffer =
+			device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+				label: Some("Top Black Rectangle Vertex Buffer"),
+				contents: bytemuck::cast_slice(&top_black_rectangle_mesh),
+				usage: wgpu::BufferUsages::VERTEX,
+			});
+
+		let color = [0.0, 0.0, 0.0];
+		let bottom_black_rectangle_mesh = vec![
+			ShapeVertexPod { position: [-1.0, -1.0, 0.0], color },
+			ShapeVertexPod { position: [1.0, -1.0, 0.0], color },
+			ShapeVertexPod { position: [-1.0, -0.5, 0.0], color },
+			ShapeVertexPod { position: [-1.0, -0.5, 0.0], color },
+			ShapeVertexPod { position: [1.0, -1.0, 0.0], color },
+			ShapeVertexPod { position: [1.0, -0.5, 0.0], color },
+		];
+		let bottom_black_rectangle_vertex_buffer =
+			device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+				label: Some("Bottom Black Rectangle Vertex Buffer"),
+				contents: bytemuck::cast_slice(&bottom_black_rectangle_mesh),
+				usage: wgpu::BufferUsages::VERTEX,
+			});
+		Renderer {
+			device,
+			queue,
+			target_format,
+			width,
+			height,
+			z_buffer_format,
+			z_buffer_view,
+			object_shader_uniform_aspect_ratio,
+			object_shader_bind_group,
+			object_render_pipeline,
+			shape_shader_uniform_aspect_ratio,
+			shape_shader_bind_group,
+			shape_render_pipeline,
+			obstacle_mesh,
+			obstacle_vertex_buffer,
+			ship_mesh,
+			ship_vertex_buffer,
+			shot_mesh,
+			shot_vertex_buffer,
+			enemy_shot_mesh,
+			enemy_shot_vertex_buffer,
+			top_black_rectangle_mesh,
+			top_black_rectangle_vertex_buffer,
+			bottom_black_rectangle_mesh,
+			bottom_black_rectangle_vertex_buffer,
+			instance_table: InstanceTable::new(),
+			text_vertex_array: TextVertexArray::new(),
+		}
+	}
+
+	/// Makes everything anew on the given device, for the same format and size of textures.
+	/// This is the way to go on after the device ran out of memory (or the surface was lost).
+	pub fn rebuild(&mut self, device: wgpu::Device, queue: wgpu::Queue) {
+		*self = Renderer::new(device, queue, self.target_format, self.width, self.height);
+	}
+
+	pub fn device(&self) -> &wgpu::Device {
+		&self.device
+	}
+
+	/// To be called when the size of the textures to render into changes.
+	pub fn resize(&mut self, width: u32, height: u32) {
+		self.width = width;
+		self.height = height;
+		self.z_buffer_view =
+			make_z_buffer_texture_view(&self.device, self.z_buffer_format, width, height);
+		let aspect_ratio = width as f32 / height as f32;
+		self.queue.write_buffer(
+			&self.object_shader_uniform_aspect_ratio.buffer,
+			0,
+			bytemuck::cast_slice(&[aspect_ratio]),
+		);
+		self.queue.write_buffer(
+			&self.shape_shader_uniform_aspect_ratio.buffer,
+			0,
+			bytemuck::cast_slice(&[aspect_ratio]),
+		);
+	}
+
+	/// Renders the given objects (and the black bands above and below the playfield) and then the
+	/// given texts into the given texture, that must be of the format and size the renderer was
+	/// made for.
+	///
+	/// Only running out of memory while the frame is recorded is caught: nothing is submitted and
+	/// the error is returned, the renderer then has to be rebuilt on a new device. The other errors
+	/// (validation errors, that are bugs, and the errors of the submission itself, which is where
+	/// a lost device shows up) are not caught and wgpu panics on them.
+	pub fn render(
+		&mut self,
+		target_view: &wgpu::TextureView,
+		objects: &[RenderedObject],
+		texts: &[RenderedText],
+	) -> Result<(), wgpu::Error> {
+		self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+		// Everything is uploaded first, the uploads are done before the commands of the frame run.
+		self.upload_objects(objects);
+		self
+			.text_vertex_array
+			.upload(&self.device, &self.queue, texts);
+
+		let mut encoder = self
+			.device
+			.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Frame Encoder") });
+		// Clearing is done by the render pass itself.
+		let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Frame Render Pass"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: target_view,
+				resolve_target: None,
+				ops: wgpu::Operations { load: wgpu::LoadOp::Clear(Renderer::CLEAR_COLOR), store: true },
+			})],
+			depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+				view: &self.z_buffer_view,
+				depth_ops: Some(wgpu::Operations { load: wgpu::LoadOp::Clear(1.0), store: true }),
+				stencil_ops: None,
+			}),
+		});
+		for stage in FrameStage::ORDER {
+			render_pass.push_debug_group(stage.label());
+			match stage {
+				FrameStage::Objects => self.draw_objects(&mut render_pass),
+				FrameStage::Overlay => self.draw_overlay(&mut render_pass),
+			}
+			render_pass.pop_debug_group();
+		}
+		// Release `render_pass.parent` which is a ref mut to `encoder`.
+		drop(render_pass);
+		let commands = encoder.finish();
+
+		if let Some(error) = futures::executor::block_on(self.device.pop_error_scope()) {
+			return Err(error);
+		}
+		self.queue.submit(std::iter::once(commands));
+		Ok(())
+	}
+
+	fn upload_objects(&mut self, objects: &[RenderedObject]) {
+		self.instance_table.clear();
+		for object in objects {
+			self.instance_table.insert_new_instance(
+				object.mesh,
+				ObjectInstancePod {
+					position: [object.position.x, object.position.y],
+					angle: object.angle,
+					scale: object.scale,
+					shade_sensitivity: object.shade_sensitivity,
+				},
+			);
+		}
+		for (&mesh, instance_array) in self.instance_table.table.iter_mut() {
+			instance_array.upload(&self.device, &self.queue, mesh);
+		}
+	}
+
+	fn draw_objects<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+		render_pass.set_pipeline(&self.object_render_pipeline);
+		render_pass.set_bind_group(0, &self.object_shader_bind_group, &[]);
+
+		for mesh in WhichMesh::all_meshes() {
+			let instance_count = self.instance_table.instance_array_len(mesh).unwrap();
+			if instance_count == 0 {
+				continue;
+			}
+			let mesh_buffer = match mesh {
+				WhichMesh::Obstacle => &self.obstacle_vertex_buffer,
+				WhichMesh::Ship => &self.ship_vertex_buffer,
+				WhichMesh::Shot => &self.shot_vertex_buffer,
+				WhichMesh::EnemyShot => &self.enemy_shot_vertex_buffer,
+			};
+			let mesh_len = match mesh {
+				WhichMesh::Obstacle => self.obstacle_mesh.len(),
+				WhichMesh::Ship => self.ship_mesh.len(),
+				WhichMesh::Shot => self.shot_mesh.len(),
+				WhichMesh::EnemyShot => self.enemy_shot_mesh.len(),
+			};
+			render_pass.set_vertex_buffer(0, mesh_buffer.slice(..));
+			render_pass.set_vertex_buffer(
+				1,
+				self
+					.instance_table
+					.instance_array_buffer_slice(mesh)
+					.unwrap(),
+			);
+			render_pass.draw(0..(mesh_len as u32), 0..(instance_count as u32));
+		}
+	}
+
+	/// Draws the text and the black bands.
+	fn draw_overlay<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+		render_pass.set_pipeline(&self.shape_render_pipeline);
+		render_pass.set_bind_group(0, &self.shape_shader_bind_group, &[]);
+
+		// The text is at the same depth as the black bands, so it is drawn first for the bands
+		// not to be drawn over it.
+		if let Some(text_vertex_buffer_slice) = self.text_vertex_array.buffer_slice() {
+			render_pass.set_vertex_buffer(0, text_vertex_buffer_slice);
+			render_pass.draw(0..(self.text_vertex_array.vertices.len() as u32), 0..1);
+		}
+
+		render_pass.set_vertex_buffer(0, self.top_black_rectangle_vertex_buffer.slice(..));
+		render_pass.draw(0..(self.top_black_rectangle_mesh.len() as u32), 0..1);
+
+		render_pass.set_vertex_buffer(0, self.bottom_black_rectangle_vertex_buffer.slice(..));
+		render_pass.draw(0..(self.bottom_black_rectangle_mesh.len() as u32), 0..1);
+	}
+}
+
+/// The parts of a frame, recorded one after the other in the render pass of the frame (the one
+/// pass of the one command encoder of the frame). Adding a part to the frame is adding a stage here
+/// (in its place in `ORDER`) and drawing it in `Renderer::render`. A stage that would need other
+/// attachments (like post-processing) would end the pass and begin its own in the same encoder.
+#[derive(Clone, Copy, Debug)]
+enum FrameStage {
+	Objects,
+	/// The text and the black bands above and below the playfield, over the objects.
+	Overlay,
+}
+
+impl FrameStage {
+	/// The stages in the order they are rendered in.
+	const ORDER: [FrameStage; 2] = [FrameStage::Objects, FrameStage::Overlay];
+
+	fn label(self) -> &'static str {
+		match self {
+			FrameStage::Objects => "Objects",
+			FrameStage::Overlay => "Overlay",
+		}
+	}
+}
+
+/// Renders into a texture that is not displayed anywhere and that can be read back,
+/// so that the game can be rendered without any window (for screenshots and tests).
+pub struct OffscreenRenderer {
+	adapter: wgpu::Adapter,
+	renderer: Renderer,
+	texture: wgpu::Texture,
+	width: u32,
+	height: u32,
+}
+
+impl OffscreenRenderer {
+	const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+	/// Gets an adapter without needing a surface, falling back to a software adapter
+	/// (like lavapipe or llvmpipe) if there is no better one.
+	/// Returns `None` if no adapter could be found at all.
+	pub fn new(width: u32, height: u32) -> Option<OffscreenRenderer> {
+		let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+			backends: wgpu::Backends::all(),
+			dx12_shader_compiler: Default::default(),
+		});
+		let adapter = [false, true]
+			.into_iter()
+			.find_map(|force_fallback_adapter| {
+				futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+					power_preference: wgpu::PowerPreference::HighPerformance,
+					compatible_surface: None,
+					force_fallback_adapter,
+				}))
+			})?;
+		let (device, queue) = OffscreenRenderer::request_device(&adapter).ok()?;
+
+		let texture = OffscreenRenderer::create_texture(&device, width, height);
+		let renderer = Renderer::new(device, queue, OffscreenRenderer::FORMAT, width, height);
+		Some(OffscreenRenderer { adapter, renderer, texture, width, height })
+	}
+
+	fn request_device(
+		adapter: &wgpu::Adapter,
+	) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+		futures::executor::block_on(adapter.request_device(
+			&wgpu::DeviceDescriptor {
+				features: wgpu::Features::empty(),
+				limits: wgpu::Limits::downlevel_defaults(),
+				label: None,
+			},
+			None,
+		))
+	}
+
+	fn create_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+		device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Offscreen Texture"),
+			size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: OffscreenRenderer::FORMAT,
+			view_formats: &[],
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+		})
+	}
+
+	/// Gets a new device from the same adapter and makes everything anew on it, as the game does
+	/// when its device runs out of memory.
+	pub fn rebuild(&mut self) -> Result<(), wgpu::RequestDeviceError> {
+		let (device, queue) = OffscreenRenderer::request_device(&self.adapter)?;
+		self.texture = OffscreenRenderer::create_texture(&device, self.width, self.height);
+		self.renderer.rebuild(device, queue);
+		Ok(())
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/// Renders the given objects and texts and returns the resulting image, as rows of RGBA
+	/// pixels (sRGB encoded) from top to bottom. The errors are the ones of `Renderer::render`.
+	pub fn render(
+		&mut self,
+		objects: &[RenderedObject],
+		texts: &[RenderedText],
+	) -> Result<Vec<u8>, wgpu::Error> {
+		let view = self
+			.texture
+			.create_view(&wgpu::TextureViewDescriptor::default());
+		self.renderer.render(&view, objects, texts)?;
+
+		// Rows in the buffer must be aligned, so there may be some padding at the end of each row.
+		let unpadded_bytes_per_row = self.width * 4;
+		let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+		let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+		let device = &self.renderer.device;
+		let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("Offscreen Readback Buffer"),
+			size: (padded_bytes_per_row * self.height) as wgpu::BufferAddress,
+			usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+			mapped_at_creation: false,
+		});
+		let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("Offscreen Readback Encoder"),
+		});
+		encoder.copy_texture_to_buffer(
+			self.texture.as_image_copy(),
+			wgpu::ImageCopyBuffer {
+				buffer: &buffer,
+				layout: wgpu::ImageDataLayout {
+					offset: 0,
+					bytes_per_row: Some(padded_bytes_per_row),
+					rows_per_image: Some(self.height),
+				},
+			},
+			wgpu::Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+		);
+		self
+			.renderer
+			.queue
+			.submit(std::iter::once(encoder.finish()));
+
+		let buffer_slice = buffer.slice(..);
+		let (sender, receiver) = std::sync::mpsc::channel();
+		buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+			sender.send(result).unwrap();
+		});
+		device.poll(wgpu::Maintain::Wait);
+		receiver.recv().unwrap().unwrap();
+
+		let padded_data = buffer_slice.get_mapped_range();
+		let mut data = Vec::with_capacity((unpadded_bytes_per_row * self.height) as usize);
+		for padded_row in padded_data.chunks(padded_bytes_per_row as usize) {
+			data.extend_from_slice(&padded_row[..unpadded_bytes_per_row as usize]);
+		}
+		drop(padded_data);
+		buffer.unmap();
+		Ok(data)
+	}
+}
+
+/// Saves an image (as returned by `OffscreenRenderer::render`) as a PNG file.
+pub fn save_png(
+	path: impl AsRef<std::path::Path>,
+	width: u32,
+	height: u32,
+	rgba: &[u8],
+) -> Result<(), png::EncodingError> {
+	let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+	let mut encoder = png::Encoder::new(file, width, height);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+	encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(rgba)?;
+	writer.finish()
+}
@@ -0,0 +1,208 @@
+//! Replay files, that contain everything needed to replay a game: the level, the seed, and the
+//! input of every step of the simulation.
+//!
+//! The format is a header followed by one record per step, all numbers being little-endian:
+//! - header: the `MAGIC` bytes, the format version as a `u16`, the level definition (see
+//!   `Level::to_source`) as its length in bytes as a `u32` followed by that much UTF-8, and the
+//!   seed as a `u64`;
+//! - step record: a `u8` of flags (see the `FLAG_*` constants) followed, only if the cursor moved
+//!   since the previous step, by the cursor position as two `f32`s.
+//!
+//! There is no step count in the header so that the file can be written as the game goes.
+//!
+//! Version 1 replays, that only knew about the bundled levels, had the level number as a `u8`
+//! instead of the level definition. They can still be read.
+
+use std::io::{Read, Write};
+
+use crate::{
+	level::{Level, LevelError},
+	world::Input,
+};
+
+const MAGIC: &[u8; 8] = b"GRAVINYR";
+const VERSION: u16 = 2;
+
+const FLAG_SHOOTING: u8 = 1 << 0;
+const FLAG_IMPULSE: u8 = 1 << 1;
+const FLAG_RESTART: u8 = 1 << 2;
+const FLAG_CURSOR_MOVED: u8 = 1 << 3;
+
+#[derive(Debug)]
+pub enum ReplayError {
+	Io(std::io::Error),
+	NotAReplay,
+	UnsupportedVersion(u16),
+	/// The level number of a version 1 replay is not the number of a bundled level.
+	InvalidLevel(u8),
+	/// The level definition embedded in the replay is not valid.
+	Level(LevelError),
+	InvalidFlags(u8),
+	/// The file ends in the middle of a step record.
+	Truncated,
+}
+
+impl std::fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ReplayError::Io(error) => write!(f, "{error}"),
+			ReplayError::NotAReplay => write!(f, "not a replay file"),
+			ReplayError::UnsupportedVersion(version) => {
+				write!(f, "unsupported replay format version {version}")
+			},
+			ReplayError::InvalidLevel(level) => write!(f, "invalid level {level}"),
+			ReplayError::Level(error) => write!(f, "{error}"),
+			ReplayError::InvalidFlags(flags) => write!(f, "invalid step flags {flags:#010b}"),
+			ReplayError::Truncated => write!(f, "the replay ends in the middle of a step"),
+		}
+	}
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+	fn from(error: std::io::Error) -> ReplayError {
+		ReplayError::Io(error)
+	}
+}
+
+/// Writes the inputs of a game into a replay, step by step.
+pub struct ReplayRecorder<W: Write> {
+	writer: W,
+	last_cursor_position: cgmath::Point2<f32>,
+}
+
+impl ReplayRecorder<std::io::BufWriter<std::fs::File>> {
+	pub fn create(
+		path: impl AsRef<std::path::Path>,
+		level: &Level,
+		seed: u64,
+	) -> std::io::Result<Self> {
+		let file = std::fs::File::create(path)?;
+		ReplayRecorder::new(std::io::BufWriter::new(file), level, seed)
+	}
+}
+
+impl<W: Write> ReplayRecorder<W> {
+	pub fn new(mut writer: W, level: &Level, seed: u64) -> std::io::Result<Self> {
+		writer.write_all(MAGIC)?;
+		writer.write_all(&VERSION.to_le_bytes())?;
+		let level_source = level.to_source();
+		writer.write_all(&(level_source.len() as u32).to_le_bytes())?;
+		writer.write_all(level_source.as_bytes())?;
+		writer.write_all(&seed.to_le_bytes())?;
+		// The world starts with the cursor at the origin (see `Input::default`).
+		let last_cursor_position = Input::default().cursor_position;
+		Ok(ReplayRecorder { writer, last_cursor_position })
+	}
+
+	/// Records the input given to one step of the simulation.
+	pub fn record(&mut self, input: &Input) -> std::io::Result<()> {
+		let cursor_moved = input.cursor_position != self.last_cursor_position;
+		let mut flags = 0;
+		if input.shooting {
+			flags |= FLAG_SHOOTING;
+		}
+		if input.impulse {
+			flags |= FLAG_IMPULSE;
+		}
+		if input.restart {
+			flags |= FLAG_RESTART;
+		}
+		if cursor_moved {
+			flags |= FLAG_CURSOR_MOVED;
+		}
+		self.writer.write_all(&[flags])?;
+		if cursor_moved {
+			let coordinates: [f32; 2] = input.cursor_position.into();
+			for coordinate in coordinates {
+				self.writer.write_all(&coordinate.to_le_bytes())?;
+			}
+			self.last_cursor_position = input.cursor_position;
+		}
+		Ok(())
+	}
+
+	pub fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+/// A whole replay, loaded in memory.
+pub struct Replay {
+	pub level: Level,
+	pub seed: u64,
+	/// The input of each step, in order.
+	pub inputs: Vec<Input>,
+}
+
+impl Replay {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Result<Replay, ReplayError> {
+		let file = std::fs::File::open(path)?;
+		Replay::read(std::io::BufReader::new(file))
+	}
+
+	pub fn read(mut reader: impl Read) -> Result<Replay, ReplayError> {
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes)?;
+		let mut bytes = bytes.as_slice();
+
+		fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+			if bytes.len() < N {
+				return None;
+			}
+			let (taken, rest) = bytes.split_at(N);
+			*bytes = rest;
+			Some(taken.try_into().unwrap())
+		}
+
+		let magic: [u8; 8] = take(&mut bytes).ok_or(ReplayError::NotAReplay)?;
+		if &magic != MAGIC {
+			return Err(ReplayError::NotAReplay);
+		}
+		let version = u16::from_le_bytes(take(&mut bytes).ok_or(ReplayError::Truncated)?);
+		let level = match version {
+			1 => {
+				let [number] = take(&mut bytes).ok_or(ReplayError::Truncated)?;
+				Level::find_bundled(&number.to_string()).ok_or(ReplayError::InvalidLevel(number))?
+			},
+			VERSION => {
+				let length = u32::from_le_bytes(take(&mut bytes).ok_or(ReplayError::Truncated)?);
+				let length = length as usize;
+				if bytes.len() < length {
+					return Err(ReplayError::Truncated);
+				}
+				let (level_source, rest) = bytes.split_at(length);
+				bytes = rest;
+				let level_source = std::str::from_utf8(level_source).map_err(|_| {
+					ReplayError::Level(LevelError::Invalid("not valid UTF-8".to_string()))
+				})?;
+				Level::parse(level_source).map_err(ReplayError::Level)?
+			},
+			_ => return Err(ReplayError::UnsupportedVersion(version)),
+		};
+		let seed = u64::from_le_bytes(take(&mut bytes).ok_or(ReplayError::Truncated)?);
+
+		let mut inputs = Vec::new();
+		let mut cursor_position = Input::default().cursor_position;
+		while let Some([flags]) = take(&mut bytes) {
+			let all_flags = FLAG_SHOOTING | FLAG_IMPULSE | FLAG_RESTART | FLAG_CURSOR_MOVED;
+			if flags & !all_flags != 0 {
+				return Err(ReplayError::InvalidFlags(flags));
+			}
+			if flags & FLAG_CURSOR_MOVED != 0 {
+				let x = f32::from_le_bytes(take(&mut bytes).ok_or(ReplayError::Truncated)?);
+				let y = f32::from_le_bytes(take(&mut bytes).ok_or(ReplayError::Truncated)?);
+				cursor_position = (x, y).into();
+			}
+			inputs.push(Input {
+				cursor_position,
+				shooting: flags & FLAG_SHOOTING != 0,
+				impulse: flags & FLAG_IMPULSE != 0,
+				restart: flags & FLAG_RESTART != 0,
+			});
+		}
+
+		Ok(Replay { level, seed, inputs })
+	}
+}
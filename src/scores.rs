@@ -0,0 +1,200 @@
+//! High scores, kept in a file in the user's data directory so that they outlive the game.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+	pub score: u32,
+	/// When the game ended, in seconds since the Unix epoch.
+	pub date: u64,
+	/// How long the game lasted, in seconds of simulation.
+	pub duration: f32,
+	#[serde(default)]
+	pub name: Option<String>,
+}
+
+impl ScoreEntry {
+	/// An entry for a game that just ended.
+	pub fn now(score: u32, duration: std::time::Duration, name: Option<String>) -> ScoreEntry {
+		let date = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.map_or(0, |since_epoch| since_epoch.as_secs());
+		ScoreEntry { score, date, duration: duration.as_secs_f32(), name }
+	}
+}
+
+/// The best entries of one level, the games played with a chosen seed having their own tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScoreTable {
+	/// As given by `Level::high_scores_key`.
+	level: String,
+	seed: Option<u64>,
+	/// The best entry first.
+	entries: Vec<ScoreEntry>,
+}
+
+/// All the high score tables. Failing to read or write them is never fatal, at worst the scores
+/// are lost (and a warning is logged).
+#[derive(Debug, Default)]
+pub struct HighScores {
+	/// Where the scores are saved, if anywhere.
+	path: Option<PathBuf>,
+	tables: Vec<ScoreTable>,
+}
+
+impl HighScores {
+	/// How many entries are kept per table.
+	pub const TABLE_LEN: usize = 10;
+
+	/// Where the scores are kept by default, if the platform has a data directory.
+	pub fn default_path() -> Option<PathBuf> {
+		Some(dirs::data_dir()?.join("gravinyon").join("scores.json"))
+	}
+
+	/// Loads the scores from the given file. A missing file just means that there are no scores
+	/// yet, and a corrupted file is put aside (with a `.corrupted` extension) to start anew.
+	pub fn load(path: impl AsRef<Path>) -> HighScores {
+		let path = path.as_ref();
+		let tables = match std::fs::read(path) {
+			Ok(bytes) => match serde_json::from_slice(&bytes) {
+				Ok(tables) => tables,
+				Err(error) => {
+					let corrupted_path = path.with_extension("json.corrupted");
+					log::warn!(
+						"The high scores file \"{}\" is corrupted ({error}), it is moved to \"{}\"",
+						path.display(),
+						corrupted_path.display()
+					);
+					if let Err(error) = std::fs::rename(path, &corrupted_path) {
+						log::warn!("Failed to move the corrupted high scores file: {error}");
+					}
+					Vec::new()
+				},
+			},
+			Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+			Err(error) => {
+				log::warn!(
+					"Failed to read the high scores file \"{}\": {error}",
+					path.display()
+				);
+				Vec::new()
+			},
+		};
+		HighScores { path: Some(path.to_path_buf()), tables }
+	}
+
+	/// Scores that are not saved anywhere.
+	pub fn in_memory() -> HighScores {
+		HighScores::default()
+	}
+
+	/// Writes the scores to their file (if they have one). The file is replaced at once so that
+	/// it is never left half written.
+	pub fn save(&self) -> std::io::Result<()> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+		if let Some(directory) = path.parent() {
+			std::fs::create_dir_all(directory)?;
+		}
+		let temporary_path = path.with_extension("json.tmp");
+		std::fs::write(&temporary_path, serde_json::to_vec_pretty(&self.tables)?)?;
+		std::fs::rename(&temporary_path, path)
+	}
+
+	/// The entries of the given level (its `Level::high_scores_key`) and seed (`None` for games
+	/// with a random seed), best first.
+	pub fn entries(&self, level: &str, seed: Option<u64>) -> &[ScoreEntry] {
+		self
+			.tables
+			.iter()
+			.find(|table| table.level == level && table.seed == seed)
+			.map_or(&[], |table| &table.entries)
+	}
+
+	pub fn best(&self, level: &str, seed: Option<u64>) -> Option<u32> {
+		self.entries(level, seed).first().map(|entry| entry.score)
+	}
+
+	/// Adds the entry if it is good enough to be in its table,
+	/// and then returns its rank in the table (starting at 0).
+	pub fn add(&mut self, level: &str, seed: Option<u64>, entry: ScoreEntry) -> Option<usize> {
+		let table_index = match self
+			.tables
+			.iter()
+			.position(|table| table.level == level && table.seed == seed)
+		{
+			Some(table_index) => table_index,
+			None => {
+				self
+					.tables
+					.push(ScoreTable { level: level.to_string(), seed, entries: Vec::new() });
+				self.tables.len() - 1
+			},
+		};
+		let entries = &mut self.tables[table_index].entries;
+		// Among equal scores, the older entries stay in front.
+		let rank = entries.partition_point(|other_entry| other_entry.score >= entry.score);
+		if rank >= HighScores::TABLE_LEN {
+			return None;
+		}
+		entries.insert(rank, entry);
+		entries.truncate(HighScores::TABLE_LEN);
+		Some(rank)
+	}
+
+	/// All the tables, as the level name, the seed, and the entries (best first).
+	pub fn tables(&self) -> impl Iterator<Item = (&str, Option<u64>, &[ScoreEntry])> {
+		self
+			.tables
+			.iter()
+			.map(|table| (table.level.as_str(), table.seed, table.entries.as_slice()))
+	}
+}
+
+/// Prints a table of entries, one per line.
+pub fn print_score_entries(entries: &[ScoreEntry]) {
+	if entries.is_empty() {
+		println!("  (no scores yet)");
+	}
+	for (rank, entry) in entries.iter().enumerate() {
+		let line = format!(
+			"  {:>2}. {:>5}  {}  {:>6.1}s  {}",
+			rank + 1,
+			entry.score,
+			format_date(entry.date),
+			entry.duration,
+			entry.name.as_deref().unwrap_or("")
+		);
+		println!("{}", line.trim_end());
+	}
+}
+
+/// Formats a date given in seconds since the Unix epoch as `YYYY-MM-DD HH:MM` (UTC).
+fn format_date(seconds_since_epoch: u64) -> String {
+	let days = (seconds_since_epoch / 86400) as i64;
+	let minutes_in_day = (seconds_since_epoch % 86400) / 60;
+	// Converting days to a proleptic Gregorian date, see
+	// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+	let days = days + 719468;
+	let era = days.div_euclid(146097);
+	let day_of_era = days.rem_euclid(146097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+	format!(
+		"{year:04}-{month:02}-{day:02} {:02}:{:02}",
+		minutes_in_day / 60,
+		minutes_in_day % 60
+	)
+}
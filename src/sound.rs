@@ -0,0 +1,595 @@
+//! Sound effects, decoded (or synthesized) once at startup and played with the volume settings of
+//! the player, and the music (see the `music` module).
+//!
+//! Effects are heard from the ship: panned by where they happen on the x axis and quieter the
+//! farther away they are, with a bit of pitch variation so that repeated effects don't all
+//! sound the same.
+//!
+//! Sound files are optional: each effect has a synthesized sound that is played when there is no
+//! file for it. Sound is never essential either: without an audio device, the game runs silently
+//! (and a warning is logged).
+
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use cgmath::MetricSpace;
+use rand::Rng;
+use rodio::Source;
+use serde::Deserialize;
+
+use crate::{
+	music::{Intensity, Music},
+	synth::{Envelope, Synth, Waveform},
+	world::WorldEvent,
+};
+
+/// A sound effect, played when some event happens in the world.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundEffect {
+	Shot,
+	Hit,
+	Explosion,
+	EnemyShot,
+	Impulse,
+	GameOver,
+	Restart,
+}
+
+impl SoundEffect {
+	pub const ALL: [SoundEffect; 7] = [
+		SoundEffect::Shot,
+		SoundEffect::Hit,
+		SoundEffect::Explosion,
+		SoundEffect::EnemyShot,
+		SoundEffect::Impulse,
+		SoundEffect::GameOver,
+		SoundEffect::Restart,
+	];
+
+	/// The effect that plays when the event happens.
+	pub fn of_event(event: &WorldEvent) -> SoundEffect {
+		match event {
+			WorldEvent::ShotFired { .. } => SoundEffect::Shot,
+			WorldEvent::ShotHit { .. } => SoundEffect::Hit,
+			WorldEvent::ObstacleDestroyed { .. } => SoundEffect::Explosion,
+			WorldEvent::EnemyShotSpawned { .. } => SoundEffect::EnemyShot,
+			WorldEvent::Impulse { .. } => SoundEffect::Impulse,
+			WorldEvent::GameOver { .. } => SoundEffect::GameOver,
+			WorldEvent::Restart => SoundEffect::Restart,
+		}
+	}
+
+	/// The file that is played instead of the synthesized sound, if it is in the sounds directory.
+	pub fn file_name(self) -> &'static str {
+		match self {
+			SoundEffect::Shot => "pew.wav",
+			SoundEffect::Hit => "hit.wav",
+			SoundEffect::Explosion => "explosion.wav",
+			SoundEffect::EnemyShot => "enemy_shot.wav",
+			SoundEffect::Impulse => "impulse.wav",
+			SoundEffect::GameOver => "game_over.wav",
+			SoundEffect::Restart => "restart.wav",
+		}
+	}
+
+	/// The synthesized sound of the effect, played when there is no file for it.
+	pub fn synth(self) -> Synth {
+		match self {
+			SoundEffect::Shot => Synth {
+				waveform: Waveform::Square,
+				start_frequency: 1200.0,
+				end_frequency: 300.0,
+				noise: 0.1,
+				envelope: Envelope {
+					attack: 0.002,
+					decay: 0.06,
+					sustain_level: 0.3,
+					sustain: 0.02,
+					release: 0.04,
+				},
+			},
+			SoundEffect::Hit => Synth {
+				waveform: Waveform::Triangle,
+				start_frequency: 320.0,
+				end_frequency: 160.0,
+				noise: 0.35,
+				envelope: Envelope {
+					attack: 0.001,
+					decay: 0.05,
+					sustain_level: 0.2,
+					sustain: 0.0,
+					release: 0.05,
+				},
+			},
+			SoundEffect::Explosion => Synth {
+				waveform: Waveform::Sawtooth,
+				start_frequency: 110.0,
+				end_frequency: 30.0,
+				noise: 0.75,
+				envelope: Envelope {
+					attack: 0.005,
+					decay: 0.15,
+					sustain_level: 0.5,
+					sustain: 0.1,
+					release: 0.35,
+				},
+			},
+			SoundEffect::EnemyShot => Synth {
+				waveform: Waveform::Sine,
+				start_frequency: 500.0,
+				end_frequency: 900.0,
+				noise: 0.0,
+				envelope: Envelope {
+					attack: 0.005,
+					decay: 0.04,
+					sustain_level: 0.4,
+					sustain: 0.0,
+					release: 0.04,
+				},
+			},
+			SoundEffect::Impulse => Synth {
+				waveform: Waveform::Sine,
+				start_frequency: 120.0,
+				end_frequency: 360.0,
+				noise: 0.5,
+				envelope: Envelope {
+					attack: 0.03,
+					decay: 0.1,
+					sustain_level: 0.3,
+					sustain: 0.0,
+					release: 0.1,
+				},
+			},
+			SoundEffect::GameOver => Synth {
+				waveform: Waveform::Square,
+				start_frequency: 440.0,
+				end_frequency: 55.0,
+				noise: 0.2,
+				envelope: Envelope {
+					attack: 0.01,
+					decay: 0.2,
+					sustain_level: 0.6,
+					sustain: 0.5,
+					release: 0.5,
+				},
+			},
+			SoundEffect::Restart => Synth {
+				waveform: Waveform::Triangle,
+				start_frequency: 220.0,
+				end_frequency: 880.0,
+				noise: 0.0,
+				envelope: Envelope {
+					attack: 0.01,
+					decay: 0.15,
+					sustain_level: 0.5,
+					sustain: 0.05,
+					release: 0.15,
+				},
+			},
+		}
+	}
+
+	/// How loud the effect is compared to the others, before the volume settings apply.
+	fn gain(self) -> f32 {
+		match self {
+			SoundEffect::Shot => 0.2,
+			SoundEffect::Hit => 0.2,
+			SoundEffect::Explosion => 0.3,
+			SoundEffect::EnemyShot => 0.15,
+			SoundEffect::Impulse => 0.2,
+			SoundEffect::GameOver => 0.4,
+			SoundEffect::Restart => 0.3,
+		}
+	}
+}
+
+/// The `sound` table of the config file. The volumes can only be set there, muting is the one
+/// thing that can also be done while playing.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundSettings {
+	/// The volume of everything, from 0 to 1.
+	pub master_volume: f32,
+	/// The volume of the sound effects, from 0 to 1 (then scaled by the master volume).
+	pub effects_volume: f32,
+	/// The volume of the music, from 0 to 1 (then scaled by the master volume).
+	pub music_volume: f32,
+	pub mute: bool,
+	/// The sound files to play instead of the default ones, relative to the sounds directory.
+	pub files: BTreeMap<SoundEffect, PathBuf>,
+}
+
+impl Default for SoundSettings {
+	fn default() -> SoundSettings {
+		SoundSettings {
+			master_volume: 1.0,
+			effects_volume: 1.0,
+			music_volume: 1.0,
+			mute: false,
+			files: BTreeMap::new(),
+		}
+	}
+}
+
+impl SoundSettings {
+	/// Checks that the volumes are in range, the error being the name of the first that is not.
+	pub fn validate(&self) -> Result<(), &'static str> {
+		for (name, volume) in [
+			("master_volume", self.master_volume),
+			("effects_volume", self.effects_volume),
+			("music_volume", self.music_volume),
+		] {
+			if !(0.0..=1.0).contains(&volume) {
+				return Err(name);
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A decoded sound, ready to be played any number of times (the samples are shared by all the
+/// times it plays, not copied).
+#[derive(Clone, Debug)]
+pub struct Sound {
+	pub channels: u16,
+	pub sample_rate: u32,
+	pub samples: Arc<[f32]>,
+}
+
+impl Sound {
+	pub fn load(path: impl AsRef<Path>) -> Result<Sound, String> {
+		let file = std::fs::File::open(path).map_err(|error| error.to_string())?;
+		let decoder =
+			rodio::Decoder::new(std::io::BufReader::new(file)).map_err(|error| error.to_string())?;
+		let channels = decoder.channels();
+		let sample_rate = decoder.sample_rate();
+		let samples = decoder.convert_samples().collect();
+		Ok(Sound { channels, sample_rate, samples })
+	}
+
+	pub fn synthesize(synth: &Synth) -> Sound {
+		Sound {
+			channels: 1,
+			sample_rate: Synth::SAMPLE_RATE,
+			samples: synth.render().into(),
+		}
+	}
+
+	pub(crate) fn source(&self) -> SoundSource {
+		SoundSource {
+			samples: Arc::clone(&self.samples),
+			channels: self.channels,
+			sample_rate: self.sample_rate,
+			sample_index: 0,
+		}
+	}
+}
+
+/// Plays a sound from its shared samples.
+pub(crate) struct SoundSource {
+	samples: Arc<[f32]>,
+	channels: u16,
+	sample_rate: u32,
+	sample_index: usize,
+}
+
+impl Iterator for SoundSource {
+	type Item = f32;
+
+	fn next(&mut self) -> Option<f32> {
+		let sample = *self.samples.get(self.sample_index)?;
+		self.sample_index += 1;
+		Some(sample)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = self.samples.len() - self.sample_index;
+		(remaining, Some(remaining))
+	}
+}
+
+impl ExactSizeIterator for SoundSource {}
+
+impl Source for SoundSource {
+	fn current_frame_len(&self) -> Option<usize> {
+		Some(self.samples.len() - self.sample_index)
+	}
+
+	fn channels(&self) -> u16 {
+		self.channels
+	}
+
+	fn sample_rate(&self) -> u32 {
+		self.sample_rate
+	}
+
+	fn total_duration(&self) -> Option<Duration> {
+		let frames = self.samples.len() / self.channels as usize;
+		Some(Duration::from_secs_f64(
+			frames as f64 / self.sample_rate as f64,
+		))
+	}
+}
+
+/// How an effect is played, with the volume settings already applied.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Voice {
+	pub effect: SoundEffect,
+	pub left_volume: f32,
+	pub right_volume: f32,
+	/// How fast the sound is played, which also changes its pitch.
+	pub speed: f32,
+}
+
+impl Voice {
+	fn loudness(&self) -> f32 {
+		self.left_volume.max(self.right_volume)
+	}
+}
+
+/// A source that counts as a playing voice for as long as it is alive (the output drops it
+/// when it is finished).
+struct CountedVoice<S> {
+	source: S,
+	playing_voices: Arc<AtomicUsize>,
+}
+
+impl<S> CountedVoice<S> {
+	fn new(source: S, playing_voices: &Arc<AtomicUsize>) -> CountedVoice<S> {
+		playing_voices.fetch_add(1, Ordering::Relaxed);
+		CountedVoice { source, playing_voices: Arc::clone(playing_voices) }
+	}
+}
+
+impl<S> Drop for CountedVoice<S> {
+	fn drop(&mut self) {
+		self.playing_voices.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+impl<S: Source<Item = f32>> Iterator for CountedVoice<S> {
+	type Item = f32;
+
+	fn next(&mut self) -> Option<f32> {
+		self.source.next()
+	}
+}
+
+impl<S: Source<Item = f32>> Source for CountedVoice<S> {
+	fn current_frame_len(&self) -> Option<usize> {
+		self.source.current_frame_len()
+	}
+
+	fn channels(&self) -> u16 {
+		self.source.channels()
+	}
+
+	fn sample_rate(&self) -> u32 {
+		self.source.sample_rate()
+	}
+
+	fn total_duration(&self) -> Option<Duration> {
+		self.source.total_duration()
+	}
+}
+
+/// The audio device, that must be kept alive for as long as sound is played.
+struct AudioOutput {
+	_stream: rodio::OutputStream,
+	stream_handle: rodio::OutputStreamHandle,
+}
+
+pub struct SoundManager {
+	output: Option<AudioOutput>,
+	effects: HashMap<SoundEffect, Sound>,
+	synthesized: HashSet<SoundEffect>,
+	music: Music,
+	settings: SoundSettings,
+	playing_voices: Arc<AtomicUsize>,
+}
+
+impl SoundManager {
+	/// How many effects can play at the same time, the new ones being dropped beyond that.
+	pub const MAX_VOICES: usize = 16;
+	/// How many effects can start during one frame. The loudest are kept, and an effect starts
+	/// at most once per frame.
+	pub const MAX_VOICES_PER_FRAME: usize = 6;
+	/// How much faster or slower than normal an effect can be played.
+	const PITCH_VARIATION: f32 = 0.06;
+	/// How much quieter effects get with the distance from the ship.
+	const ATTENUATION: f32 = 1.5;
+	/// How far to the sides effects are panned at the edges of the screen (1 being fully).
+	const STEREO_WIDTH: f32 = 0.7;
+
+	/// Where the sound files are by default.
+	pub fn default_directory() -> PathBuf {
+		PathBuf::from("assets/sounds")
+	}
+
+	/// Opens the default audio device and loads the effects from the given directory.
+	pub fn new(settings: SoundSettings, directory: impl AsRef<Path>) -> SoundManager {
+		let output = match rodio::OutputStream::try_default() {
+			Ok((stream, stream_handle)) => Some(AudioOutput { _stream: stream, stream_handle }),
+			Err(error) => {
+				log::warn!("No audio output ({error}), the game will be silent");
+				None
+			},
+		};
+		let mut sound_manager = SoundManager { output, ..SoundManager::silent(settings, directory) };
+		if let Some(output) = &sound_manager.output {
+			sound_manager.music.start(&output.stream_handle);
+		}
+		sound_manager
+	}
+
+	/// Loads the effects and the music from the given directory, without ever playing them (like
+	/// when there is no audio device). The files given in the settings replace the default ones.
+	pub fn silent(settings: SoundSettings, directory: impl AsRef<Path>) -> SoundManager {
+		let directory = directory.as_ref();
+		let mut effects = HashMap::new();
+		let mut synthesized = HashSet::new();
+		for effect in SoundEffect::ALL {
+			let configured_file = settings.files.get(&effect);
+			let path =
+				directory.join(configured_file.map_or(Path::new(effect.file_name()), PathBuf::as_path));
+			// Without a file for the effect, that is just fine, there is the synthesized sound.
+			let sound = if configured_file.is_none() && !path.exists() {
+				None
+			} else {
+				match Sound::load(&path) {
+					Ok(sound) => Some(sound),
+					Err(error) => {
+						log::warn!(
+							"Failed to load the sound \"{}\" ({error}), a synthesized sound will be played instead",
+							path.display()
+						);
+						None
+					},
+				}
+			};
+			let sound = sound.unwrap_or_else(|| {
+				synthesized.insert(effect);
+				Sound::synthesize(&effect.synth())
+			});
+			effects.insert(effect, sound);
+		}
+		SoundManager {
+			output: None,
+			effects,
+			synthesized,
+			music: Music::load(directory),
+			settings,
+			playing_voices: Arc::default(),
+		}
+	}
+
+	pub fn has_output(&self) -> bool {
+		self.output.is_some()
+	}
+
+	/// Is the effect played with its synthesized sound, rather than with a file.
+	pub fn is_synthesized(&self, effect: SoundEffect) -> bool {
+		self.synthesized.contains(&effect)
+	}
+
+	/// How many effects are playing right now.
+	pub fn playing_voices(&self) -> usize {
+		self.playing_voices.load(Ordering::Relaxed)
+	}
+
+	pub fn settings(&self) -> &SoundSettings {
+		&self.settings
+	}
+
+	pub fn music(&self) -> &Music {
+		&self.music
+	}
+
+	/// Makes the music follow the intensity of the game, to be called every frame with the time
+	/// elapsed since the last frame.
+	pub fn update_music(&mut self, intensity: &Intensity, game_over: bool, elapsed: Duration) {
+		let volume = if self.settings.mute {
+			0.0
+		} else {
+			self.settings.music_volume * self.settings.master_volume
+		};
+		self
+			.music
+			.update(intensity.level(), game_over, volume, elapsed);
+	}
+
+	pub fn set_muted(&mut self, mute: bool) {
+		self.settings.mute = mute;
+	}
+
+	/// How loud the effect is played with the current settings.
+	pub fn volume(&self, effect: SoundEffect) -> f32 {
+		if self.settings.mute {
+			0.0
+		} else {
+			effect.gain() * self.settings.effects_volume * self.settings.master_volume
+		}
+	}
+
+	/// How the effect of something that happened at the given position (or nowhere in particular)
+	/// is played, as heard from the listener.
+	pub fn voice(
+		&self,
+		effect: SoundEffect,
+		position: Option<cgmath::Point2<f32>>,
+		listener: cgmath::Point2<f32>,
+	) -> Voice {
+		let volume = self.volume(effect);
+		let (pan, attenuation) = match position {
+			Some(position) => (
+				(position.x / 1.1).clamp(-1.0, 1.0) * SoundManager::STEREO_WIDTH,
+				1.0 / (1.0 + SoundManager::ATTENUATION * position.distance(listener)),
+			),
+			None => (0.0, 1.0),
+		};
+		let variation = SoundManager::PITCH_VARIATION;
+		Voice {
+			effect,
+			left_volume: volume * attenuation * (1.0 - pan).min(1.0),
+			right_volume: volume * attenuation * (1.0 + pan).min(1.0),
+			speed: rand::thread_rng().gen_range((1.0 - variation)..=(1.0 + variation)),
+		}
+	}
+
+	/// The voices to play for the events of a frame, the loudest first.
+	pub fn voices(&self, events: &[WorldEvent], listener: cgmath::Point2<f32>) -> Vec<Voice> {
+		let mut voices: Vec<_> = events
+			.iter()
+			.map(|event| self.voice(SoundEffect::of_event(event), event.position(), listener))
+			.filter(|voice| voice.loudness() > 0.0)
+			.collect();
+		voices.sort_by(|a, b| b.loudness().total_cmp(&a.loudness()));
+		let mut effects = Vec::new();
+		voices.retain(|voice| {
+			let new_effect = !effects.contains(&voice.effect);
+			effects.push(voice.effect);
+			new_effect
+		});
+		voices.truncate(SoundManager::MAX_VOICES_PER_FRAME);
+		voices
+	}
+
+	/// Plays the effects of the events of a frame, as heard from the listener.
+	pub fn play_events(&self, events: &[WorldEvent], listener: cgmath::Point2<f32>) {
+		for voice in self.voices(events, listener) {
+			self.play_voice(&voice);
+		}
+	}
+
+	/// Plays the effect as if it happened where the listener is.
+	pub fn play(&self, effect: SoundEffect) {
+		self.play_voice(&self.voice(effect, None, (0.0, 0.0).into()));
+	}
+
+	pub fn play_voice(&self, voice: &Voice) {
+		let (Some(output), Some(sound)) = (&self.output, self.effects.get(&voice.effect)) else {
+			return;
+		};
+		if voice.loudness() <= 0.0 || self.playing_voices() >= SoundManager::MAX_VOICES {
+			return;
+		}
+		// The channels of the sound are summed before being panned.
+		let channels = sound.channels as f32;
+		let source = rodio::source::ChannelVolume::new(
+			sound.source().speed(voice.speed),
+			vec![voice.left_volume / channels, voice.right_volume / channels],
+		);
+		let source = CountedVoice::new(source, &self.playing_voices);
+		if let Err(error) = output.stream_handle.play_raw(source) {
+			log::warn!("Failed to play a sound: {error}");
+		}
+	}
+}
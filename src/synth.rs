@@ -0,0 +1,181 @@
+//! A tiny synthesizer, so that the game has sound effects without any sound file.
+//!
+//! A sound is an oscillator (whose pitch can sweep from one frequency to another) mixed with some
+//! white noise, shaped by an envelope. The noise is not random between runs, rendering the same
+//! parameters always gives the same samples.
+
+use std::time::Duration;
+
+use rodio::Source;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Waveform {
+	Sine,
+	Square,
+	Triangle,
+	Sawtooth,
+}
+
+impl Waveform {
+	/// The value of the wave at the given phase, in periods (`0.0..1.0` being the first period).
+	pub fn value(self, phase: f32) -> f32 {
+		let phase = phase.fract();
+		match self {
+			Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+			Waveform::Square => {
+				if phase < 0.5 {
+					1.0
+				} else {
+					-1.0
+				}
+			},
+			Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+			Waveform::Sawtooth => 2.0 * phase - 1.0,
+		}
+	}
+}
+
+/// How loud a sound is over time, all the durations being in seconds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Envelope {
+	/// From silence to full volume.
+	pub attack: f32,
+	/// From full volume to the sustain level.
+	pub decay: f32,
+	pub sustain_level: f32,
+	/// How long the sustain level is held.
+	pub sustain: f32,
+	/// From the sustain level to silence.
+	pub release: f32,
+}
+
+impl Envelope {
+	pub fn duration(&self) -> f32 {
+		self.attack + self.decay + self.sustain + self.release
+	}
+
+	/// The volume at the given time, from 0 to 1.
+	pub fn volume(&self, time: f32) -> f32 {
+		// Dividing by zero durations is avoided by never getting into their part.
+		let mut time = time;
+		if time < self.attack {
+			return time / self.attack;
+		}
+		time -= self.attack;
+		if time < self.decay {
+			return 1.0 - (1.0 - self.sustain_level) * time / self.decay;
+		}
+		time -= self.decay;
+		if time < self.sustain {
+			return self.sustain_level;
+		}
+		time -= self.sustain;
+		if time < self.release {
+			return self.sustain_level * (1.0 - time / self.release);
+		}
+		0.0
+	}
+}
+
+/// The parameters of a synthesized sound.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Synth {
+	pub waveform: Waveform,
+	/// The frequency at the beginning, in Hz.
+	pub start_frequency: f32,
+	/// The frequency at the end, in Hz, the pitch sweeping exponentially from the start one.
+	pub end_frequency: f32,
+	/// How much of the sound is white noise instead of the oscillator, from 0 to 1.
+	pub noise: f32,
+	pub envelope: Envelope,
+}
+
+impl Synth {
+	pub const SAMPLE_RATE: u32 = 44100;
+
+	pub fn duration(&self) -> Duration {
+		Duration::from_secs_f32(self.envelope.duration())
+	}
+
+	/// The sound as a mono source.
+	pub fn source(&self) -> SynthSource {
+		let sample_count = (self.envelope.duration() * Synth::SAMPLE_RATE as f32).ceil() as usize;
+		SynthSource {
+			synth: *self,
+			sample_index: 0,
+			sample_count,
+			phase: 0.0,
+			noise_state: 0x9e37_79b9,
+		}
+	}
+
+	/// All the samples of the sound, at `Synth::SAMPLE_RATE`.
+	pub fn render(&self) -> Vec<f32> {
+		self.source().collect()
+	}
+}
+
+pub struct SynthSource {
+	synth: Synth,
+	sample_index: usize,
+	sample_count: usize,
+	/// The phase of the oscillator, in periods.
+	phase: f32,
+	/// The state of the xorshift generator of the noise.
+	noise_state: u32,
+}
+
+impl SynthSource {
+	fn next_noise(&mut self) -> f32 {
+		self.noise_state ^= self.noise_state << 13;
+		self.noise_state ^= self.noise_state >> 17;
+		self.noise_state ^= self.noise_state << 5;
+		self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+	}
+}
+
+impl Iterator for SynthSource {
+	type Item = f32;
+
+	fn next(&mut self) -> Option<f32> {
+		if self.sample_index >= self.sample_count {
+			return None;
+		}
+		let Synth { waveform, start_frequency, end_frequency, noise, envelope } = self.synth;
+		let time = self.sample_index as f32 / Synth::SAMPLE_RATE as f32;
+		let progress = self.sample_index as f32 / self.sample_count as f32;
+		let frequency = start_frequency * (end_frequency / start_frequency).powf(progress);
+
+		let tone = waveform.value(self.phase);
+		let sample = (tone * (1.0 - noise) + self.next_noise() * noise) * envelope.volume(time);
+
+		self.phase = (self.phase + frequency / Synth::SAMPLE_RATE as f32).fract();
+		self.sample_index += 1;
+		Some(sample)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = self.sample_count - self.sample_index;
+		(remaining, Some(remaining))
+	}
+}
+
+impl ExactSizeIterator for SynthSource {}
+
+impl Source for SynthSource {
+	fn current_frame_len(&self) -> Option<usize> {
+		Some(self.sample_count - self.sample_index)
+	}
+
+	fn channels(&self) -> u16 {
+		1
+	}
+
+	fn sample_rate(&self) -> u32 {
+		Synth::SAMPLE_RATE
+	}
+
+	fn total_duration(&self) -> Option<Duration> {
+		Some(self.synth.duration())
+	}
+}
@@ -0,0 +1,626 @@
+use std::f32::consts::TAU;
+
+use cgmath::{InnerSpace, MetricSpace};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+	broadphase::UniformGrid,
+	collision::PlacedMesh,
+	level::{EnemyFireRules, FireRule, Level},
+	mesh::WhichMesh,
+};
+
+/// Identifies an object for as long as it exists in the world,
+/// so that stuff outside the simulation (like rendering) can keep track of it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ObjectId(u64);
+
+pub enum Object {
+	Ship {
+		position: cgmath::Point2<f32>,
+		motion: cgmath::Vector2<f32>,
+		id: ObjectId,
+	},
+	Shot {
+		position: cgmath::Point2<f32>,
+		angle: f32,
+		id: ObjectId,
+	},
+	EnemyShot {
+		position: cgmath::Point2<f32>,
+		angle: f32,
+		id: ObjectId,
+	},
+	Obstacle {
+		position: cgmath::Point2<f32>,
+		motion: cgmath::Vector2<f32>,
+		angle: f32,
+		angle_rotation: f32,
+		scale: f32,
+		life: u32,
+		id: ObjectId,
+	},
+}
+
+impl Object {
+	pub fn is_ship(&self) -> bool {
+		matches!(self, Object::Ship { .. })
+	}
+	pub fn is_shot(&self) -> bool {
+		matches!(self, Object::Shot { .. })
+	}
+	pub fn is_enemy_shot(&self) -> bool {
+		matches!(self, Object::EnemyShot { .. })
+	}
+	pub fn is_obstacle(&self) -> bool {
+		matches!(self, Object::Obstacle { .. })
+	}
+
+	pub fn id(&self) -> ObjectId {
+		match self {
+			Object::Ship { id, .. } => *id,
+			Object::Shot { id, .. } => *id,
+			Object::EnemyShot { id, .. } => *id,
+			Object::Obstacle { id, .. } => *id,
+		}
+	}
+
+	pub fn position(&self) -> cgmath::Point2<f32> {
+		match self {
+			Object::Ship { position, .. } => *position,
+			Object::Shot { position, .. } => *position,
+			Object::EnemyShot { position, .. } => *position,
+			Object::Obstacle { position, .. } => *position,
+		}
+	}
+
+	pub const SHIP_SCALE: f32 = 0.02;
+
+	pub fn scale(&self) -> f32 {
+		match self {
+			Object::Ship { .. } => Object::SHIP_SCALE,
+			Object::Shot { .. } => 0.01,
+			Object::EnemyShot { .. } => 0.01,
+			Object::Obstacle { scale, .. } => *scale,
+		}
+	}
+
+	const SHOT_SPEED: f32 = 0.015;
+	const ENEMY_SHOT_SPEED: f32 = 0.008;
+
+	/// How far the object will move during the next step.
+	fn step_distance(&self) -> f32 {
+		match self {
+			Object::Ship { motion, .. } => motion.magnitude(),
+			Object::Shot { .. } => Object::SHOT_SPEED,
+			Object::EnemyShot { .. } => Object::ENEMY_SHOT_SPEED,
+			Object::Obstacle { motion, .. } => motion.magnitude(),
+		}
+	}
+
+	/// How far from its position the mesh of the object can cover.
+	fn bounding_radius(&self) -> f32 {
+		WhichMesh::of_object(self).bounding_radius() * self.scale()
+	}
+
+	/// The angle of the mesh of the object, the ship looking towards the cursor.
+	pub fn mesh_angle(&self, cursor_position: cgmath::Point2<f32>) -> f32 {
+		match self {
+			Object::Ship { position, .. } => {
+				let ship_to_cursor = cursor_position - *position;
+				let angle = f32::atan2(ship_to_cursor.y, ship_to_cursor.x);
+				angle - TAU / 4.0
+			},
+			Object::Shot { angle, .. } => angle - TAU / 4.0,
+			Object::EnemyShot { angle, .. } => angle - TAU / 4.0,
+			Object::Obstacle { angle, .. } => *angle,
+		}
+	}
+
+	fn placed_mesh(&self, cursor_position: cgmath::Point2<f32>) -> PlacedMesh {
+		PlacedMesh {
+			mesh: WhichMesh::of_object(self),
+			position: self.position(),
+			angle: self.mesh_angle(cursor_position),
+			scale: self.scale(),
+		}
+	}
+
+	/// Do the meshes of the two objects overlap on the screen plane.
+	pub fn collide_with(&self, other: &Object, cursor_position: cgmath::Point2<f32>) -> bool {
+		self
+			.placed_mesh(cursor_position)
+			.collides_with(&other.placed_mesh(cursor_position))
+	}
+}
+
+/// What the player is doing during one step of the simulation.
+#[derive(Clone, Copy, Debug)]
+pub struct Input {
+	/// Where the cursor is, in the same coordinates as the objects.
+	pub cursor_position: cgmath::Point2<f32>,
+	/// Is the shooting button held down.
+	pub shooting: bool,
+	/// Should the ship get an impulse towards the cursor this step.
+	pub impulse: bool,
+	/// Should the game restart this step (only has an effect when the game is over).
+	pub restart: bool,
+}
+
+impl Default for Input {
+	fn default() -> Input {
+		Input {
+			cursor_position: (0.0, 0.0).into(),
+			shooting: false,
+			impulse: false,
+			restart: false,
+		}
+	}
+}
+
+/// Something that happened during a step, for what is outside of the simulation (like sound)
+/// to react to. The positions are where it happened.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WorldEvent {
+	/// The ship shot (two shots at once).
+	ShotFired { position: cgmath::Point2<f32> },
+	/// A shot hit an obstacle that survived it.
+	ShotHit { position: cgmath::Point2<f32> },
+	/// An obstacle lost its last life.
+	ObstacleDestroyed { position: cgmath::Point2<f32> },
+	/// An obstacle shot at something.
+	EnemyShotSpawned { position: cgmath::Point2<f32> },
+	/// The ship got an impulse towards the cursor.
+	Impulse { position: cgmath::Point2<f32> },
+	/// The ship was hit.
+	GameOver { position: cgmath::Point2<f32> },
+	/// A new game started after a game over.
+	Restart,
+}
+
+impl WorldEvent {
+	pub fn position(&self) -> Option<cgmath::Point2<f32>> {
+		match self {
+			WorldEvent::ShotFired { position }
+			| WorldEvent::ShotHit { position }
+			| WorldEvent::ObstacleDestroyed { position }
+			| WorldEvent::EnemyShotSpawned { position }
+			| WorldEvent::Impulse { position }
+			| WorldEvent::GameOver { position } => Some(*position),
+			WorldEvent::Restart => None,
+		}
+	}
+}
+
+/// The whole game simulation, without anything related to windows, rendering or sound,
+/// so that it can be run headless.
+pub struct World {
+	level: Level,
+	seed: u64,
+	/// All the randomness of the simulation comes from there, so that a given seed and a given
+	/// sequence of inputs always give the same game. `ChaCha8Rng` is used (rather than `StdRng`)
+	/// because its output is guaranteed not to change between versions and platforms.
+	rng: ChaCha8Rng,
+	objects: Vec<Object>,
+	next_object_id: u64,
+	cursor_position: cgmath::Point2<f32>,
+	shooting_delay: i32,
+	/// How many steps were played in the current game, used to time the enemy fire.
+	game_steps: u64,
+	/// The events of the steps since the last time they were taken.
+	events: Vec<WorldEvent>,
+	game_over: bool,
+	score: u32,
+}
+
+impl World {
+	/// How much time a step simulates. All the speeds, delays, etc. are expressed in steps.
+	pub const STEP_DURATION: std::time::Duration =
+		std::time::Duration::from_nanos(1_000_000_000 / 60);
+
+	const SHOOTING_DELAY_MAX: i32 = 13;
+
+	pub fn new(level: Level, seed: u64) -> World {
+		let mut world = World {
+			level,
+			seed,
+			rng: ChaCha8Rng::seed_from_u64(seed),
+			objects: Vec::new(),
+			next_object_id: 0,
+			cursor_position: (0.0, 0.0).into(),
+			shooting_delay: 0,
+			game_steps: 0,
+			events: Vec::new(),
+			game_over: false,
+			score: 0,
+		};
+		world.init_objects();
+		world
+	}
+
+	pub fn level(&self) -> &Level {
+		&self.level
+	}
+
+	/// The seed of the current game. After a restart it is not the seed given to `World::new`
+	/// anymore, but it still allows to reproduce the current game from its beginning.
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// The objects, the ship always being the first one.
+	pub fn objects(&self) -> &[Object] {
+		&self.objects
+	}
+
+	/// The cursor position given by the last step input, towards which the ship is looking.
+	pub fn cursor_position(&self) -> cgmath::Point2<f32> {
+		self.cursor_position
+	}
+
+	pub fn score(&self) -> u32 {
+		self.score
+	}
+
+	/// How long the current game has lasted (not counting the time spent being game over).
+	pub fn game_duration(&self) -> std::time::Duration {
+		World::STEP_DURATION * self.game_steps as u32
+	}
+
+	pub fn is_game_over(&self) -> bool {
+		self.game_over
+	}
+
+	/// The events that happened since the last call, in order.
+	pub fn take_events(&mut self) -> Vec<WorldEvent> {
+		std::mem::take(&mut self.events)
+	}
+
+	fn new_object_id(&mut self) -> ObjectId {
+		let id = ObjectId(self.next_object_id);
+		self.next_object_id += 1;
+		id
+	}
+
+	fn spawn_obstacles(&mut self, how_many: usize) {
+		let rules = self.level.obstacles;
+		for _i in 0..how_many {
+			let obstacle = Object::Obstacle {
+				position: cgmath::Point2 {
+					x: 1.05,
+					y: self.rng.gen_range(rules.spawn_y[0]..rules.spawn_y[1]),
+				},
+				angle: self.rng.gen_range(0.0..TAU),
+				scale: self.rng.gen_range(rules.scale[0]..rules.scale[1]),
+				motion: cgmath::Vector2 {
+					x: self.rng.gen_range(rules.speed_x[0]..rules.speed_x[1]),
+					y: self.rng.gen_range(rules.speed_y[0]..rules.speed_y[1]),
+				},
+				angle_rotation: self
+					.rng
+					.gen_range(rules.rotation_speed[0]..rules.rotation_speed[1]),
+				life: rules.life,
+				id: self.new_object_id(),
+			};
+			self.objects.push(obstacle);
+		}
+	}
+
+	fn init_objects(&mut self) {
+		self.objects = Vec::new();
+		let ship = Object::Ship {
+			position: (0.0, 0.0).into(),
+			motion: (0.0, 0.0).into(),
+			id: self.new_object_id(),
+		};
+		self.objects.push(ship);
+		self.spawn_obstacles(self.level.obstacles.initial_count);
+	}
+
+	fn restart(&mut self) {
+		// The next game gets its own seed, taken from the current randomness so that
+		// the whole session stays deterministic.
+		self.seed = self.rng.gen();
+		self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+		self.shooting_delay = 0;
+		self.game_steps = 0;
+		self.game_over = false;
+		self.score = 0;
+		self.init_objects();
+	}
+
+	/// Advances the simulation by one step.
+	pub fn step(&mut self, input: &Input) {
+		self.cursor_position = input.cursor_position;
+
+		if self.game_over {
+			if input.restart {
+				self.restart();
+				self.events.push(WorldEvent::Restart);
+			}
+			return;
+		}
+
+		let cursor_position = input.cursor_position;
+
+		if let Object::Ship { position, motion, .. } = self.objects.get_mut(0).unwrap() {
+			if input.impulse {
+				let ship_to_cursor = cursor_position - *position;
+				let ship_to_cursor_angle = f32::atan2(ship_to_cursor.y, ship_to_cursor.x);
+				let force = cgmath::Vector2::<f32> {
+					x: f32::cos(ship_to_cursor_angle),
+					y: f32::sin(ship_to_cursor_angle),
+				} * 0.003;
+				*motion += force;
+				self
+					.events
+					.push(WorldEvent::Impulse { position: *position });
+			}
+
+			let ship_to_cursor = cursor_position - *position;
+
+			let ship_to_cursor_distance = ship_to_cursor.magnitude();
+			// The cursor being exactly on the ship (like at the very beginning when nothing
+			// moved yet) would make the force direction NaN and the ship would be lost forever.
+			if ship_to_cursor_distance > 0.0 {
+				let mut force = ship_to_cursor.normalize() / ship_to_cursor_distance.powi(2);
+				force *= 0.000025;
+				if force.magnitude() > 0.0001 {
+					force = force.normalize() * 0.0001;
+				}
+				*motion += force;
+			}
+		} else {
+			panic!();
+		}
+
+		if 0 <= self.shooting_delay {
+			self.shooting_delay -= 1;
+		}
+		if input.shooting && self.shooting_delay <= 0 {
+			let (ship_position, ship_direction, ship_direction_left) =
+				if let Object::Ship { position, .. } = self.objects.first().unwrap() {
+					let ship_to_cursor = cursor_position - *position;
+					let ship_to_cursor_angle = f32::atan2(ship_to_cursor.y, ship_to_cursor.x);
+					let ship_position = *position;
+					let ship_direction = cgmath::Vector2::<f32> {
+						x: f32::cos(ship_to_cursor_angle),
+						y: f32::sin(ship_to_cursor_angle),
+					};
+					let ship_direction_left = cgmath::Vector2::<f32> {
+						x: f32::cos(ship_to_cursor_angle + TAU / 4.0),
+						y: f32::sin(ship_to_cursor_angle + TAU / 4.0),
+					};
+					(ship_position, ship_direction, ship_direction_left)
+				} else {
+					panic!();
+				};
+			for i in 0..2 {
+				let position = ship_position
+					+ ship_direction * 0.035
+					+ ship_direction_left * 0.016 * ((i * 2 - 1) as f32);
+				let position_to_cursor = (cursor_position - position).normalize();
+				let position_to_cursor_angle = f32::atan2(position_to_cursor.y, position_to_cursor.x);
+				let shot = Object::Shot {
+					position,
+					angle: position_to_cursor_angle,
+					id: self.new_object_id(),
+				};
+				self.objects.push(shot);
+			}
+			self
+				.events
+				.push(WorldEvent::ShotFired { position: ship_position });
+			self.shooting_delay = World::SHOOTING_DELAY_MAX;
+		}
+
+		// Spawning more obstacles when one is taken down.
+		let mut spawn_event = false;
+
+		let mut dead_object_indices = Vec::new();
+
+		let mut new_objects = Vec::new();
+
+		// Only the pairs of objects that are close enough to interact are considered.
+		// Objects move during the loop, so the distance they can move in one step is accounted for,
+		// and objects can wrap around to the other side of the playfield which the grid does too.
+		let max_step_distance = self
+			.objects
+			.iter()
+			.map(Object::step_distance)
+			.fold(0.0, f32::max);
+		let max_collision_distance = 2.0
+			* self
+				.objects
+				.iter()
+				.map(Object::bounding_radius)
+				.fold(0.0, f32::max);
+		let enemy_fire = &self.level.enemy_fire;
+		let max_interaction_distance = [enemy_fire.at_ship, enemy_fire.at_each_other]
+			.into_iter()
+			.flatten()
+			.map(|rule| rule.range)
+			.fold(max_collision_distance, f32::max);
+		let grid = UniformGrid::new(
+			max_interaction_distance + 2.0 * max_step_distance,
+			self.objects.iter().map(Object::position),
+		);
+		let mut neighbors_of_objects = vec![Vec::new(); self.objects.len()];
+		for (a, b) in grid.candidate_pairs() {
+			neighbors_of_objects[a].push(b);
+			neighbors_of_objects[b].push(a);
+		}
+		// Going through the pairs in the same order as if all the pairs were considered.
+		for neighbors in neighbors_of_objects.iter_mut() {
+			neighbors.sort_unstable();
+		}
+
+		let game_steps = self.game_steps;
+		let fires = |rule: Option<FireRule>, distance: f32| {
+			rule.is_some_and(|rule| {
+				distance < rule.range && game_steps.is_multiple_of(rule.interval as u64)
+			})
+		};
+		let EnemyFireRules { at_ship, at_cursor, at_each_other } = self.level.enemy_fire;
+
+		'object_loop: for (object_index, neighbors) in neighbors_of_objects.iter().enumerate() {
+			let object = self.objects.get(object_index).unwrap();
+
+			let mut object_is_shot_and_dies = false;
+			let mut object_is_obstacle_and_takes_damage = 0;
+			for &other_object_index in neighbors.iter() {
+				let other_object = self.objects.get(other_object_index).unwrap();
+				if (object.is_shot() || object.is_enemy_shot())
+					&& other_object.is_obstacle()
+					&& object.collide_with(other_object, cursor_position)
+				{
+					object_is_shot_and_dies = true;
+				} else if object.is_obstacle()
+					&& other_object.is_shot()
+					&& object.collide_with(other_object, cursor_position)
+				{
+					object_is_obstacle_and_takes_damage += 1;
+				} else if object.is_ship()
+					&& (other_object.is_obstacle() || other_object.is_enemy_shot())
+					&& object.collide_with(other_object, cursor_position)
+				{
+					if !self.game_over {
+						self
+							.events
+							.push(WorldEvent::GameOver { position: object.position() });
+					}
+					self.game_over = true;
+				} else if object.is_ship()
+					&& other_object.is_obstacle()
+					&& fires(at_ship, object.position().distance(other_object.position()))
+				{
+					let direction = (object.position() - other_object.position()).normalize();
+					let angle = f32::atan2(direction.y, direction.x);
+					let position = other_object.position() + direction * (0.02 + other_object.scale());
+					new_objects.push((position, angle));
+				} else if object.is_obstacle()
+					&& other_object.is_obstacle()
+					&& fires(
+						at_each_other,
+						object.position().distance(other_object.position()),
+					) {
+					let direction = (other_object.position() - object.position()).normalize();
+					let angle = f32::atan2(direction.y, direction.x);
+					let position = other_object.position() + direction * (0.02 + other_object.scale());
+					new_objects.push((position, angle));
+				}
+			}
+
+			if object.is_obstacle() && fires(at_cursor, object.position().distance(cursor_position)) {
+				let direction = (cursor_position - object.position()).normalize();
+				let angle = f32::atan2(direction.y, direction.x);
+				let position = object.position() + direction * (0.02 + object.scale());
+				new_objects.push((position, angle));
+			}
+
+			let object = self.objects.get_mut(object_index).unwrap();
+
+			if object_is_obstacle_and_takes_damage > 0 {
+				if let Object::Obstacle { position, life, .. } = object {
+					*life = life.saturating_sub(object_is_obstacle_and_takes_damage);
+					self.events.push(if *life == 0 {
+						WorldEvent::ObstacleDestroyed { position: *position }
+					} else {
+						WorldEvent::ShotHit { position: *position }
+					});
+				} else {
+					panic!();
+				}
+			}
+
+			if object_is_shot_and_dies || matches!(object, Object::Obstacle { life, .. } if *life == 0)
+			{
+				dead_object_indices.push(object_index);
+				if object.is_obstacle() {
+					self.score += 1;
+					spawn_event = true;
+				}
+				continue 'object_loop;
+			}
+
+			match object {
+				Object::Obstacle { position, motion, angle, angle_rotation, scale, .. } => {
+					*position += *motion;
+					*angle += *angle_rotation;
+
+					if position.x <= -1.1 {
+						position.x = 1.1;
+					} else if position.x > 1.1 {
+						position.x = -1.1;
+					}
+					if position.y < -0.5 + *scale {
+						position.y = -0.5 + *scale;
+						motion.y = f32::abs(motion.y);
+					} else if position.y > 0.5 - *scale {
+						position.y = 0.5 - *scale;
+						motion.y = -f32::abs(motion.y);
+					}
+				},
+
+				Object::Ship { position, motion, .. } => {
+					*position += *motion;
+
+					if position.x <= -1.1 {
+						position.x = 1.1;
+					} else if position.x > 1.1 {
+						position.x = -1.1;
+					}
+					if position.y < -0.5 + Object::SHIP_SCALE {
+						position.y = -0.5 + Object::SHIP_SCALE;
+						motion.y = f32::abs(motion.y);
+						*motion *= 0.95;
+					} else if position.y > 0.5 - Object::SHIP_SCALE {
+						position.y = 0.5 - Object::SHIP_SCALE;
+						motion.y = -f32::abs(motion.y);
+						*motion *= 0.95;
+					}
+				},
+
+				Object::Shot { position, angle, .. } => {
+					let motion = cgmath::Vector2::<f32> { x: f32::cos(*angle), y: f32::sin(*angle) }
+						* Object::SHOT_SPEED;
+					*position += motion;
+
+					if position.x <= -1.1 || position.x > 1.1 || position.y <= -0.6 || position.y > 0.6 {
+						dead_object_indices.push(object_index);
+						continue 'object_loop;
+					}
+				},
+
+				Object::EnemyShot { position, angle, .. } => {
+					let motion = cgmath::Vector2::<f32> { x: f32::cos(*angle), y: f32::sin(*angle) }
+						* Object::ENEMY_SHOT_SPEED;
+					*position += motion;
+
+					if position.x <= -1.1 || position.x > 1.1 || position.y <= -0.6 || position.y > 0.6 {
+						dead_object_indices.push(object_index);
+						continue 'object_loop;
+					}
+				},
+			}
+		}
+
+		dead_object_indices.sort();
+		for dead_object_index in dead_object_indices.into_iter().rev() {
+			self.objects.remove(dead_object_index);
+		}
+
+		for (position, angle) in new_objects {
+			let id = self.new_object_id();
+			self.objects.push(Object::EnemyShot { position, angle, id });
+			self.events.push(WorldEvent::EnemyShotSpawned { position });
+		}
+
+		if spawn_event {
+			self.spawn_obstacles(self.level.obstacles.respawn_count);
+		}
+
+		self.game_steps += 1;
+	}
+}
@@ -0,0 +1,143 @@
+//! The uniform grid against comparing every object with every other object.
+
+use std::collections::BTreeSet;
+
+use cgmath::MetricSpace;
+use gravinyon::UniformGrid;
+use rand::{Rng, SeedableRng};
+
+/// How far apart two points of the playfield are, going around the wrapping of the x axis
+/// (-1.1 and 1.1 being the same) if it is shorter.
+fn wrapped_distance(a: cgmath::Point2<f32>, b: cgmath::Point2<f32>) -> f32 {
+	let dx = (a.x - b.x).abs();
+	let dx = dx.min(2.2 - dx);
+	(dx * dx + (a.y - b.y) * (a.y - b.y)).sqrt()
+}
+
+/// The pairs of objects that are at most `distance` apart, found by brute force.
+fn close_pairs(positions: &[cgmath::Point2<f32>], distance: f32) -> BTreeSet<(usize, usize)> {
+	close_pairs_by(positions, distance, |a, b| a.distance(b))
+}
+
+fn close_pairs_by(
+	positions: &[cgmath::Point2<f32>],
+	distance: f32,
+	distance_between: impl Fn(cgmath::Point2<f32>, cgmath::Point2<f32>) -> f32,
+) -> BTreeSet<(usize, usize)> {
+	let mut pairs = BTreeSet::new();
+	for a in 0..positions.len() {
+		for b in (a + 1)..positions.len() {
+			if distance_between(positions[a], positions[b]) <= distance {
+				pairs.insert((a, b));
+			}
+		}
+	}
+	pairs
+}
+
+/// The candidate pairs that are at most `distance` apart, checking that no pair is given twice.
+fn close_candidate_pairs(
+	positions: &[cgmath::Point2<f32>],
+	distance: f32,
+) -> BTreeSet<(usize, usize)> {
+	close_candidate_pairs_by(positions, distance, |a, b| a.distance(b))
+}
+
+fn close_candidate_pairs_by(
+	positions: &[cgmath::Point2<f32>],
+	distance: f32,
+	distance_between: impl Fn(cgmath::Point2<f32>, cgmath::Point2<f32>) -> f32,
+) -> BTreeSet<(usize, usize)> {
+	let grid = UniformGrid::new(distance, positions.iter().copied());
+	let mut pairs = BTreeSet::new();
+	for (a, b) in grid.candidate_pairs() {
+		assert!(a < b, "({a}, {b})");
+		assert!(pairs.insert((a, b)), "({a}, {b}) is given twice");
+	}
+	pairs.retain(|&(a, b)| distance_between(positions[a], positions[b]) <= distance);
+	pairs
+}
+
+#[test]
+fn same_pairs_as_brute_force() {
+	let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+	for (count, distance) in [(30, 0.2), (300, 0.05), (1000, 0.35), (200, 3.0)] {
+		// Some objects are out of the playfield, like shots about to disappear.
+		let positions: Vec<_> = (0..count)
+			.map(|_| cgmath::Point2 { x: rng.gen_range(-1.3..1.3), y: rng.gen_range(-0.7..0.7) })
+			.collect();
+		let pairs = close_pairs(&positions, distance);
+		assert!(!pairs.is_empty());
+		assert_eq!(close_candidate_pairs(&positions, distance), pairs);
+	}
+}
+
+#[test]
+fn same_pairs_as_brute_force_around_the_wrapping() {
+	let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+	for (count, distance) in [(100, 0.2), (300, 0.05), (1000, 0.35), (200, 0.8)] {
+		// Half of the objects are close to the left or right edge of the playfield. With the
+		// biggest distance, there is only one column.
+		let positions: Vec<_> = (0..count)
+			.map(|index| {
+				let x = if index % 2 == 0 {
+					rng.gen_range(-1.1..1.1)
+				} else if rng.gen() {
+					rng.gen_range(-1.1..-1.0)
+				} else {
+					rng.gen_range(1.0..1.1)
+				};
+				cgmath::Point2 { x, y: rng.gen_range(-0.5..0.5) }
+			})
+			.collect();
+		let pairs = close_pairs_by(&positions, distance, wrapped_distance);
+		assert!(pairs
+			.iter()
+			.any(|&(a, b)| positions[a].distance(positions[b]) > distance));
+		assert_eq!(
+			close_candidate_pairs_by(&positions, distance, wrapped_distance),
+			pairs
+		);
+	}
+}
+
+#[test]
+fn object_wrapping_around_during_a_step() {
+	// An obstacle about to go past the right edge comes back on the left edge, where it can hit
+	// the ship.
+	let distance = 0.1;
+	let positions: Vec<cgmath::Point2<f32>> = vec![(1.09, 0.0).into(), (-1.08, 0.0).into()];
+	let grid = UniformGrid::new(distance, positions.iter().copied());
+	assert_eq!(grid.candidate_pairs().collect::<Vec<_>>(), vec![(0, 1)]);
+}
+
+#[test]
+fn objects_across_cell_borders() {
+	// With cells of 0.2, there are cell borders at x = -0.9 and y = -0.4 (the grid starts at
+	// -1.1 × -0.6).
+	let distance = 0.2;
+	let positions: Vec<cgmath::Point2<f32>> = vec![
+		// On both sides of a vertical border.
+		(-0.9001, 0.05).into(),
+		(-0.8999, 0.05).into(),
+		// On both sides of a horizontal border.
+		(0.3, -0.4001).into(),
+		(0.3, -0.3999).into(),
+		// On both sides of a corner, diagonally, both ways.
+		(-0.9001, -0.4001).into(),
+		(-0.8999, -0.3999).into(),
+		(-0.8999, -0.4001).into(),
+		(-0.9001, -0.3999).into(),
+		// Exactly one cell apart, in neighboring cells.
+		(0.5, 0.1).into(),
+		(0.7, 0.1).into(),
+		// Far outside the grid, in the nearest cells.
+		(5.0, 5.0).into(),
+		(5.1, 5.0).into(),
+	];
+	let pairs = close_pairs(&positions, distance);
+	for pair in [(0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11)] {
+		assert!(pairs.contains(&pair), "{pair:?}");
+	}
+	assert_eq!(close_candidate_pairs(&positions, distance), pairs);
+}
@@ -0,0 +1,107 @@
+//! Parses command lines as the game would, without running it.
+
+use std::path::PathBuf;
+
+use clap::error::ErrorKind;
+use gravinyon::{AdapterChoice, Backend, Cli, Level, PresentMode};
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+	Cli::try_parse_args_from(std::iter::once("gravinyon").chain(args.iter().copied()))
+}
+
+#[test]
+fn valid_arguments() {
+	let cli = parse(&[]).unwrap();
+	assert!(cli.level.is_none());
+	assert_eq!(cli.present_mode, None);
+	assert_eq!(cli.max_fps, None);
+
+	let cli = parse(&[
+		"three",
+		"--seed",
+		"42",
+		"--window-size",
+		"800x600",
+		"--present-mode",
+		"mailbox",
+		"--max-fps",
+		"144",
+		"--mute",
+		"--headless",
+		"100",
+	])
+	.unwrap();
+	assert_eq!(cli.level, Level::find_bundled("3"));
+	assert_eq!(cli.seed, Some(42));
+	assert_eq!(cli.window_size, Some((800, 600)));
+	assert_eq!(cli.present_mode, Some(PresentMode::Mailbox));
+	assert_eq!(cli.max_fps, Some(144.0));
+	assert!(cli.mute);
+	assert_eq!(cli.headless, Some(100));
+}
+
+#[test]
+fn adapter_selection() {
+	let cli = parse(&["--adapter", "1", "--backend", "vulkan"]).unwrap();
+	assert_eq!(cli.adapter, Some(AdapterChoice::Index(1)));
+	assert_eq!(cli.backend, Some(Backend::Vulkan));
+	let cli = parse(&["--adapter", "GeForce"]).unwrap();
+	assert_eq!(
+		cli.adapter,
+		Some(AdapterChoice::Name("GeForce".to_string()))
+	);
+	assert!(parse(&["--list-adapters", "--backend", "gl"]).is_ok());
+	assert!(parse(&["--backend", "glide"]).is_err());
+	assert!(parse(&["--list-adapters", "--adapter", "0"]).is_err());
+}
+
+#[test]
+fn screenshot_after() {
+	let cli = parse(&["--screenshot-after", "300", "end.png"]).unwrap();
+	assert_eq!(cli.headless, Some(300));
+	assert_eq!(cli.screenshot, Some(PathBuf::from("end.png")));
+	let cli = parse(&["--headless", "300", "--screenshot", "end.png"]).unwrap();
+	assert_eq!(cli.headless, Some(300));
+	assert_eq!(cli.screenshot, Some(PathBuf::from("end.png")));
+}
+
+#[test]
+fn invalid_arguments() {
+	let error_kind = |args: &[&str]| parse(args).unwrap_err().kind();
+	assert_eq!(error_kind(&["five"]), ErrorKind::ValueValidation);
+	assert_eq!(error_kind(&["--seed", "x"]), ErrorKind::ValueValidation);
+	assert_eq!(
+		error_kind(&["--window-size", "800x0"]),
+		ErrorKind::ValueValidation
+	);
+	assert_eq!(
+		error_kind(&["--present-mode", "vsync"]),
+		ErrorKind::InvalidValue
+	);
+	assert_eq!(error_kind(&["--max-fps", "0"]), ErrorKind::ValueValidation);
+	assert_eq!(
+		error_kind(&["--max-fps", "1e-20"]),
+		ErrorKind::ValueValidation
+	);
+	assert_eq!(error_kind(&["--frobnicate"]), ErrorKind::UnknownArgument);
+	assert_eq!(
+		error_kind(&["--replay", "a.gry", "--seed", "1"]),
+		ErrorKind::ArgumentConflict
+	);
+	assert_eq!(
+		error_kind(&["--screenshot", "a.png"]),
+		ErrorKind::MissingRequiredArgument
+	);
+	assert_eq!(
+		error_kind(&["--screenshot-after", "x", "a.png"]),
+		ErrorKind::ValueValidation
+	);
+	assert_eq!(
+		error_kind(&["--screenshot-after", "10"]),
+		ErrorKind::WrongNumberOfValues
+	);
+	assert_eq!(
+		error_kind(&["--screenshot-after", "10", "a.png", "--headless", "10"]),
+		ErrorKind::ArgumentConflict
+	);
+}
@@ -0,0 +1,96 @@
+//! Collisions between the meshes as they are placed on the screen plane.
+
+use std::f32::consts::TAU;
+
+use gravinyon::{triangles_intersect, PlacedMesh, Triangle2, WhichMesh};
+
+fn triangle(points: [(f32, f32); 3]) -> Triangle2 {
+	points.map(cgmath::Point2::from)
+}
+
+fn placed(mesh: WhichMesh, position: (f32, f32), angle: f32, scale: f32) -> PlacedMesh {
+	PlacedMesh { mesh, position: position.into(), angle, scale }
+}
+
+/// What the collision check did before using the meshes: comparing the distance between the
+/// centers with the sum of the scales.
+fn circles_collide(a: &PlacedMesh, b: &PlacedMesh) -> bool {
+	cgmath::MetricSpace::distance(a.position, b.position) < a.scale + b.scale
+}
+
+#[test]
+fn triangles() {
+	let a = triangle([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+	let overlapping = triangle([(0.2, 0.2), (2.0, 0.2), (0.2, 2.0)]);
+	let inside = triangle([(0.1, 0.1), (0.3, 0.1), (0.1, 0.3)]);
+	let separated = triangle([(0.6, 0.6), (2.0, 0.6), (0.6, 2.0)]);
+	let sharing_an_edge = triangle([(1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]);
+	let sharing_a_vertex = triangle([(1.0, 0.0), (2.0, 0.0), (2.0, 1.0)]);
+	for (other, intersect) in [
+		(a, true),
+		(overlapping, true),
+		(inside, true),
+		(separated, false),
+		(sharing_an_edge, false),
+		(sharing_a_vertex, false),
+	] {
+		assert_eq!(triangles_intersect(&a, &other), intersect, "{other:?}");
+		assert_eq!(triangles_intersect(&other, &a), intersect, "{other:?}");
+	}
+
+	// The vertices going the other way around does not matter.
+	let [p, q, r] = overlapping;
+	assert!(triangles_intersect(&a, &[r, q, p]));
+	// A degenerate triangle (a segment) crossing the triangle.
+	let segment = triangle([(-1.0, 0.5), (1.0, 0.5), (1.0, 0.5)]);
+	assert!(triangles_intersect(&a, &segment));
+}
+
+#[test]
+fn shot_tip_hits_what_the_circles_missed() {
+	let obstacle = placed(WhichMesh::Obstacle, (0.0, -0.03), 0.0, 0.025);
+	// The long tail of the shot points down, and reaches into the top of the obstacle.
+	let shot = placed(WhichMesh::Shot, (0.0, 0.05), 0.0, 0.01);
+	assert!(!circles_collide(&shot, &obstacle));
+	assert!(shot.collides_with(&obstacle));
+	assert!(obstacle.collides_with(&shot));
+
+	// A bit higher and it does not reach anymore.
+	let shot = placed(WhichMesh::Shot, (0.0, 0.07), 0.0, 0.01);
+	assert!(!shot.collides_with(&obstacle));
+}
+
+#[test]
+fn circles_hit_that_the_meshes_miss() {
+	// The pentagon does not go as far on the left as its circle does, and the shot is thin.
+	let obstacle = placed(WhichMesh::Obstacle, (0.0, 0.0), 0.0, 0.025);
+	let shot = placed(WhichMesh::Shot, (-0.03, 0.0), 0.0, 0.01);
+	assert!(circles_collide(&shot, &obstacle));
+	assert!(!shot.collides_with(&obstacle));
+
+	// Same with the ship, whose notch at the back leaves room.
+	let ship = placed(WhichMesh::Ship, (0.0, 0.0), 0.0, 0.02);
+	let enemy_shot = placed(WhichMesh::EnemyShot, (0.0, -0.027), 0.0, 0.01);
+	assert!(circles_collide(&ship, &enemy_shot));
+	assert!(!ship.collides_with(&enemy_shot));
+}
+
+#[test]
+fn rotated_meshes() {
+	let obstacle = placed(WhichMesh::Obstacle, (0.0, 0.0), 0.0, 0.025);
+	// Standing up, the shot is too thin to reach the obstacle.
+	let shot = placed(WhichMesh::Shot, (0.05, 0.0), 0.0, 0.01);
+	assert!(!shot.collides_with(&obstacle));
+	// Lying down with its tail towards the obstacle, it reaches it, but not with the tail the other
+	// way.
+	let shot = placed(WhichMesh::Shot, (0.05, 0.0), -TAU / 4.0, 0.01);
+	assert!(shot.collides_with(&obstacle));
+	let shot = placed(WhichMesh::Shot, (0.05, 0.0), TAU / 4.0, 0.01);
+	assert!(!shot.collides_with(&obstacle));
+
+	// A vertex of the pentagon points right, turning it by half a period points an edge instead.
+	let shot = placed(WhichMesh::Shot, (0.03, 0.0), -TAU / 4.0, 0.001);
+	assert!(shot.collides_with(&obstacle));
+	let turned_obstacle = placed(WhichMesh::Obstacle, (0.0, 0.0), TAU / 10.0, 0.025);
+	assert!(!shot.collides_with(&turned_obstacle));
+}
@@ -0,0 +1,128 @@
+//! Drives the controls with synthetic input events, as a window would.
+
+use gravinyon::{
+	Action, Bindings, Button, Config, Controller, InputEvent, MouseButton, VirtualKeyCode,
+};
+
+fn press(controller: &mut Controller, button: Button) {
+	controller.handle_event(InputEvent::Button { button, pressed: true });
+}
+
+fn release(controller: &mut Controller, button: Button) {
+	controller.handle_event(InputEvent::Button { button, pressed: false });
+}
+
+#[test]
+fn mouse_scheme() {
+	let mut controller = Controller::new(Bindings::default());
+	controller.handle_event(InputEvent::CursorMoved((0.3, -0.2).into()));
+	press(&mut controller, Button::Mouse(MouseButton::Left));
+	press(&mut controller, Button::Mouse(MouseButton::Right));
+
+	let input = controller.next_step_input();
+	assert_eq!(input.cursor_position, (0.3, -0.2).into());
+	assert!(input.shooting);
+	assert!(input.impulse);
+
+	// The impulse is given once, shooting goes on while the button is held.
+	let input = controller.next_step_input();
+	assert!(input.shooting);
+	assert!(!input.impulse);
+
+	release(&mut controller, Button::Mouse(MouseButton::Left));
+	assert!(!controller.next_step_input().shooting);
+}
+
+#[test]
+fn keyboard_scheme_moves_the_virtual_aim_point() {
+	let mut controller = Controller::new(Bindings::default());
+	let start = controller.next_step_input().cursor_position;
+
+	press(&mut controller, Button::Key(VirtualKeyCode::D));
+	let after_one_step = controller.next_step_input().cursor_position;
+	assert!(after_one_step.x > start.x);
+	assert_eq!(after_one_step.y, start.y);
+
+	// Holding the aim keys for long enough brings the aim point to the edge of the playfield.
+	press(&mut controller, Button::Key(VirtualKeyCode::Up));
+	for _ in 0..1000 {
+		controller.next_step_input();
+	}
+	assert_eq!(controller.aim_position(), (1.0, 0.5).into());
+
+	release(&mut controller, Button::Key(VirtualKeyCode::D));
+	release(&mut controller, Button::Key(VirtualKeyCode::Up));
+	let stopped = controller.next_step_input().cursor_position;
+	assert_eq!(controller.next_step_input().cursor_position, stopped);
+
+	// Moving the mouse takes the aim back to the cursor.
+	controller.handle_event(InputEvent::CursorMoved((-0.5, 0.1).into()));
+	assert_eq!(
+		controller.next_step_input().cursor_position,
+		(-0.5, 0.1).into()
+	);
+}
+
+#[test]
+fn key_repeat_is_not_a_new_press() {
+	let mut controller = Controller::new(Bindings::default());
+	press(&mut controller, Button::Key(VirtualKeyCode::LShift));
+	assert!(controller.next_step_input().impulse);
+	press(&mut controller, Button::Key(VirtualKeyCode::LShift));
+	assert!(!controller.next_step_input().impulse);
+}
+
+#[test]
+fn losing_focus_releases_everything() {
+	let mut controller = Controller::new(Bindings::default());
+	press(&mut controller, Button::Key(VirtualKeyCode::Space));
+	assert!(controller.next_step_input().shooting);
+	controller.release_all();
+	assert!(!controller.next_step_input().shooting);
+}
+
+#[test]
+fn config_rebinds_and_falls_back_to_defaults() {
+	let config = Config::parse(
+		"[controls]\n\
+		fire = [\"Return\", \"MouseLeft\"]\n\
+		quit = [\"Q\", \"Mouse4\"]\n",
+	)
+	.unwrap();
+	let bindings = &config.bindings;
+	assert_eq!(
+		bindings.buttons(Action::Fire),
+		[
+			Button::Mouse(MouseButton::Left),
+			Button::Key(VirtualKeyCode::Return)
+		]
+	);
+	assert_eq!(
+		bindings.buttons(Action::Quit),
+		[
+			Button::Mouse(MouseButton::Other(4)),
+			Button::Key(VirtualKeyCode::Q)
+		]
+	);
+	// Space is not bound to firing anymore, and restart can't keep Return that is taken.
+	assert_eq!(bindings.action(Button::Key(VirtualKeyCode::Space)), None);
+	assert_eq!(bindings.buttons(Action::Restart), []);
+	// The other actions keep their defaults.
+	assert_eq!(
+		bindings.buttons(Action::Impulse),
+		Bindings::default().buttons(Action::Impulse)
+	);
+}
+
+#[test]
+fn config_errors() {
+	let error = |source: &str| Config::parse(source).unwrap_err().to_string();
+	assert!(error("[controls]\nfier = [\"Space\"]").contains("unknown action `fier`"));
+	assert!(error("[controls]\nfire = [\"Spcae\"]").contains("unknown button `Spcae`"));
+	assert!(
+		error("[controls]\nfire = [\"Space\"]\nimpulse = [\"Space\"]")
+			.contains("button `Space` is bound to both `fire` and `impulse`")
+	);
+	assert!(error("[constrols]").contains("unknown field"));
+	assert!(Config::parse("").is_ok());
+}
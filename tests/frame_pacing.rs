@@ -0,0 +1,65 @@
+//! The present mode fallbacks and the frame rate cap.
+
+use std::time::{Duration, Instant};
+
+use gravinyon::{Config, FrameLimiter, PresentMode};
+
+#[test]
+fn present_mode_falls_back_to_a_supported_one() {
+	use wgpu::PresentMode as Mode;
+	let all = [Mode::Fifo, Mode::Mailbox, Mode::Immediate];
+	assert_eq!(PresentMode::Mailbox.choose(&all), Mode::Mailbox);
+	assert_eq!(
+		PresentMode::Mailbox.choose(&[Mode::Fifo, Mode::Immediate]),
+		Mode::Immediate
+	);
+	assert_eq!(PresentMode::Immediate.choose(&[Mode::Fifo]), Mode::Fifo);
+	// Some backends have no Fifo.
+	assert_eq!(
+		PresentMode::Fifo.choose(&[Mode::Immediate, Mode::Mailbox]),
+		Mode::Mailbox
+	);
+	assert_eq!(
+		PresentMode::Fifo.choose(&[Mode::AutoVsync]),
+		Mode::AutoVsync
+	);
+}
+
+#[test]
+fn frame_limiter_keeps_the_pace() {
+	let start = Instant::now();
+	let frame = Duration::from_millis(10);
+	let mut frame_limiter = FrameLimiter::new(100.0);
+	assert_eq!(frame_limiter.start_frame(start), Ok(()));
+	let next_frame = frame_limiter.start_frame(start + frame / 2).unwrap_err();
+	assert_eq!(next_frame, start + frame);
+
+	// Very late, the next frame is right away but there is no burst of frames after it.
+	let late = start + frame * 10;
+	assert_eq!(frame_limiter.start_frame(late), Ok(()));
+	assert_eq!(frame_limiter.start_frame(late), Err(late + frame));
+}
+
+#[test]
+fn graphics_config() {
+	let config = Config::parse("[graphics]\npresent_mode = \"immediate\"\nmax_fps = 240\n").unwrap();
+	assert_eq!(config.graphics.present_mode, Some(PresentMode::Immediate));
+	assert_eq!(config.graphics.max_fps, Some(240.0));
+	assert_eq!(Config::parse("").unwrap().graphics.present_mode, None);
+	assert!(Config::parse("[graphics]\npresent_mode = \"vsync\"").is_err());
+	assert!(Config::parse("[graphics]\nmax_fps = -1").is_err());
+	assert!(Config::parse("[graphics]\nmax_fps = 1e-20").is_err());
+	assert!(Config::parse("[graphics]\nmax_fps = 1").is_ok());
+}
+
+#[test]
+fn frame_limiter_with_a_tiny_cap() {
+	// Such caps are rejected by the settings, but they don't crash the limiter.
+	for max_fps in [1e-20, 0.0, -5.0, f64::NAN] {
+		let start = Instant::now();
+		let mut frame_limiter = FrameLimiter::new(max_fps);
+		assert_eq!(frame_limiter.start_frame(start), Ok(()));
+		let next_frame = frame_limiter.start_frame(start).unwrap_err();
+		assert_eq!(next_frame, start + Duration::from_secs(1));
+	}
+}
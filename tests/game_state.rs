@@ -0,0 +1,134 @@
+//! Drives the game state machine with actions, as the controls would.
+
+use gravinyon::{
+	Action, GameState, HighScores, Level, LevelSelect, MenuItem, ScoreEntry, StateCommand,
+};
+
+fn title() -> GameState {
+	GameState::Title(LevelSelect::new(
+		&Level::bundled(),
+		&HighScores::in_memory(),
+		0,
+	))
+}
+
+#[test]
+fn title_starts_a_game() {
+	let mut state = title();
+	assert!(!state.is_simulating());
+	assert_eq!(state.handle_action(Action::Impulse), None);
+	assert_eq!(state, title());
+	assert_eq!(
+		state.handle_action(Action::Fire),
+		Some(StateCommand::StartGame(0))
+	);
+	assert_eq!(state, GameState::Playing);
+	assert!(state.is_simulating());
+}
+
+#[test]
+fn level_select() {
+	let levels = Level::bundled();
+	let mut high_scores = HighScores::in_memory();
+	high_scores.add(
+		&levels[1].name,
+		None,
+		ScoreEntry::now(42, std::time::Duration::ZERO, None),
+	);
+	// Games with a chosen seed don't count.
+	high_scores.add(
+		&levels[2].name,
+		Some(7),
+		ScoreEntry::now(42, std::time::Duration::ZERO, None),
+	);
+	let level_select = LevelSelect::new(&levels, &high_scores, 0);
+	let best_scores: Vec<_> = level_select
+		.entries
+		.iter()
+		.map(|entry| entry.best_score)
+		.collect();
+	assert_eq!(best_scores, [None, Some(42), None, None]);
+	assert_eq!(level_select.entries[3].description, levels[3].description);
+
+	// The selection stays in the list.
+	let mut state = GameState::Title(level_select);
+	for _ in 0..10 {
+		state.handle_action(Action::AimDown);
+	}
+	state.handle_action(Action::AimUp);
+	assert_eq!(
+		state.clone().handle_action(Action::Restart),
+		Some(StateCommand::StartGame(2))
+	);
+
+	// The cursor selects the level it is over.
+	state.hover((0.0, 0.2).into());
+	assert_eq!(
+		state.handle_action(Action::Fire),
+		Some(StateCommand::StartGame(0))
+	);
+}
+
+#[test]
+fn game_over_goes_back_to_the_title() {
+	let mut state = GameState::GameOver;
+	assert_eq!(state.handle_action(Action::Fire), None);
+	assert_eq!(
+		state.handle_action(Action::Quit),
+		Some(StateCommand::LeaveGame)
+	);
+}
+
+#[test]
+fn pausing_freezes_the_simulation() {
+	let mut state = GameState::Playing;
+	assert_eq!(state.handle_action(Action::Pause), None);
+	assert!(matches!(state, GameState::Paused(_)));
+	assert!(!state.is_simulating());
+	// Pausing again resumes.
+	assert_eq!(state.handle_action(Action::Pause), None);
+	assert_eq!(state, GameState::Playing);
+
+	// Losing focus pauses, but there is nothing to pause on the title screen.
+	state.pause();
+	assert!(matches!(state, GameState::Paused(_)));
+	let mut state = title();
+	state.pause();
+	assert_eq!(state, title());
+}
+
+#[test]
+fn pause_menu() {
+	let mut state = GameState::Playing;
+	state.handle_action(Action::Quit);
+	let GameState::Paused(menu) = &state else {
+		panic!("not paused");
+	};
+	assert_eq!(menu.items[menu.selected], MenuItem::Resume);
+
+	// The selection stays in the menu.
+	state.handle_action(Action::AimUp);
+	state.handle_action(Action::AimDown);
+	state.handle_action(Action::AimDown);
+	state.handle_action(Action::AimDown);
+	state.handle_action(Action::AimDown);
+	let GameState::Paused(menu) = &state else {
+		panic!("not paused");
+	};
+	assert_eq!(menu.items[menu.selected], MenuItem::Quit);
+	assert_eq!(
+		state.clone().handle_action(Action::Fire),
+		Some(StateCommand::Quit)
+	);
+
+	// The cursor selects the item it is over.
+	state.hover((0.0, -0.01).into());
+	let GameState::Paused(menu) = &state else {
+		panic!("not paused");
+	};
+	assert_eq!(menu.items[menu.selected], MenuItem::BackToTitle);
+	assert_eq!(
+		state.handle_action(Action::Restart),
+		Some(StateCommand::LeaveGame)
+	);
+}
@@ -0,0 +1,200 @@
+//! Renders each mesh (and some text) offscreen and compares the result with a reference image
+//! in `tests/golden`.
+//!
+//! Run with `GRAVINYON_BLESS=1` to (re)generate the reference images after an intended change
+//! in the rendering. These tests are skipped when no adapter is available at all
+//! (a software adapter such as lavapipe or llvmpipe is enough).
+
+use gravinyon::{
+	save_png, OffscreenRenderer, RenderedObject, RenderedText, TextAlignment, WhichMesh,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+/// How different a channel of a pixel can be before the pixel is considered different,
+/// different drivers do not rasterize and blend in exactly the same way.
+const CHANNEL_TOLERANCE: u8 = 8;
+/// How many pixels can be different before the images are considered different.
+const MAX_DIFFERENT_PIXELS_RATIO: f32 = 0.01;
+
+fn check_golden(name: &str, objects: &[RenderedObject], texts: &[RenderedText]) {
+	let Some(mut offscreen_renderer) = OffscreenRenderer::new(WIDTH, HEIGHT) else {
+		eprintln!("No adapter available, skipping the golden image test \"{name}\"");
+		return;
+	};
+	let image = offscreen_renderer.render(objects, texts).unwrap();
+	compare_with_golden(name, &image);
+}
+
+fn compare_with_golden(name: &str, image: &[u8]) {
+	let golden_path = format!("{}/tests/golden/{name}.png", env!("CARGO_MANIFEST_DIR"));
+	if std::env::var_os("GRAVINYON_BLESS").is_some() {
+		save_png(&golden_path, WIDTH, HEIGHT, image).unwrap();
+		return;
+	}
+
+	let golden_file = match std::fs::File::open(&golden_path) {
+		Ok(file) => file,
+		Err(error) => panic!("Failed to open \"{golden_path}\" ({error}), bless it maybe?"),
+	};
+	let mut golden_reader = png::Decoder::new(golden_file).read_info().unwrap();
+	let mut golden = vec![0; golden_reader.output_buffer_size()];
+	let golden_info = golden_reader.next_frame(&mut golden).unwrap();
+	assert_eq!((golden_info.width, golden_info.height), (WIDTH, HEIGHT));
+	assert_eq!(golden_info.color_type, png::ColorType::Rgba);
+
+	let different_pixels = image
+		.chunks(4)
+		.zip(golden.chunks(4))
+		.filter(|(pixel, golden_pixel)| {
+			pixel
+				.iter()
+				.zip(golden_pixel.iter())
+				.any(|(channel, golden_channel)| channel.abs_diff(*golden_channel) > CHANNEL_TOLERANCE)
+		})
+		.count();
+	let different_pixels_ratio = different_pixels as f32 / (WIDTH * HEIGHT) as f32;
+	if different_pixels_ratio > MAX_DIFFERENT_PIXELS_RATIO {
+		let actual_path = format!("{}/{name}.actual.png", env!("CARGO_TARGET_TMPDIR"));
+		save_png(&actual_path, WIDTH, HEIGHT, image).unwrap();
+		panic!(
+			"{different_pixels} pixels differ from \"{golden_path}\", \
+			the actual rendering was saved to \"{actual_path}\""
+		);
+	}
+}
+
+fn single_object(mesh: WhichMesh, scale: f32, shade_sensitivity: f32) -> [RenderedObject; 1] {
+	[RenderedObject {
+		mesh,
+		position: (0.0, 0.0).into(),
+		angle: 0.4,
+		scale,
+		shade_sensitivity,
+	}]
+}
+
+#[test]
+fn obstacle_mesh() {
+	check_golden(
+		"obstacle",
+		&single_object(WhichMesh::Obstacle, 0.3, 3.0),
+		&[],
+	);
+}
+
+#[test]
+fn ship_mesh() {
+	check_golden("ship", &single_object(WhichMesh::Ship, 0.3, 3.0), &[]);
+}
+
+#[test]
+fn shot_mesh() {
+	check_golden("shot", &single_object(WhichMesh::Shot, 0.05, 0.0), &[]);
+}
+
+#[test]
+fn enemy_shot_mesh() {
+	check_golden(
+		"enemy_shot",
+		&single_object(WhichMesh::EnemyShot, 0.08, 0.0),
+		&[],
+	);
+}
+
+fn text(text: &str, y: f32, alignment: TextAlignment) -> RenderedText {
+	RenderedText {
+		text: text.to_string(),
+		position: (0.0, y).into(),
+		alignment,
+		height: 0.1,
+		color: [0.8, 0.8, 0.9],
+	}
+}
+
+fn golden_texts() -> [RenderedText; 3] {
+	[
+		text("Gravinyon", 0.2, TextAlignment::Center),
+		text("0123456789", 0.0, TextAlignment::Right),
+		text("-:.,!?'/", -0.2, TextAlignment::Left),
+	]
+}
+
+#[test]
+fn text_mesh() {
+	check_golden("text", &[], &golden_texts());
+}
+
+#[test]
+fn less_text_than_in_the_previous_frame() {
+	let Some(mut offscreen_renderer) = OffscreenRenderer::new(WIDTH, HEIGHT) else {
+		eprintln!("No adapter available, skipping the text buffer test");
+		return;
+	};
+	// Lots of text first so that the text vertex buffer grows, then the text that is left must be
+	// the only one drawn.
+	let lots_of_text: Vec<_> = (0..40)
+		.map(|index| {
+			text(
+				&"0123456789".repeat(4),
+				index as f32 * 0.05 - 1.0,
+				TextAlignment::Center,
+			)
+		})
+		.collect();
+	offscreen_renderer.render(&[], &lots_of_text).unwrap();
+	let image = offscreen_renderer.render(&[], &golden_texts()).unwrap();
+	compare_with_golden("text", &image);
+}
+
+#[test]
+fn fewer_objects_than_in_the_previous_frame() {
+	let Some(mut offscreen_renderer) = OffscreenRenderer::new(WIDTH, HEIGHT) else {
+		eprintln!("No adapter available, skipping the instance buffers test");
+		return;
+	};
+	// Lots of obstacles first so that the instance buffer grows, then only one of them is left
+	// and none of the others may still be drawn.
+	let obstacles: Vec<_> = (0..200)
+		.map(|index| RenderedObject {
+			mesh: WhichMesh::Obstacle,
+			position: (
+				(index % 20) as f32 * 0.1 - 1.0,
+				(index / 20) as f32 * 0.1 - 0.5,
+			)
+				.into(),
+			angle: 0.0,
+			scale: 0.04,
+			shade_sensitivity: 3.0,
+		})
+		.collect();
+	offscreen_renderer.render(&obstacles, &[]).unwrap();
+	let image = offscreen_renderer
+		.render(&single_object(WhichMesh::Obstacle, 0.3, 3.0), &[])
+		.unwrap();
+	compare_with_golden("obstacle", &image);
+}
+
+#[test]
+fn rebuilt_renderer() {
+	let Some(mut offscreen_renderer) = OffscreenRenderer::new(WIDTH, HEIGHT) else {
+		eprintln!("No adapter available, skipping the rebuilt renderer test");
+		return;
+	};
+	// The renderer gets a new device and must render the same as a new one, with none of its
+	// state (like the instance buffers) left from the device it had before.
+	offscreen_renderer
+		.render(&single_object(WhichMesh::Ship, 0.2, 1.0), &[])
+		.unwrap();
+	offscreen_renderer.rebuild().unwrap();
+	let image = offscreen_renderer
+		.render(&single_object(WhichMesh::Obstacle, 0.3, 3.0), &[])
+		.unwrap();
+	compare_with_golden("obstacle", &image);
+	offscreen_renderer.rebuild().unwrap();
+	let image = offscreen_renderer
+		.render(&single_object(WhichMesh::Obstacle, 0.3, 3.0), &[])
+		.unwrap();
+	compare_with_golden("obstacle", &image);
+}
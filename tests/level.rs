@@ -0,0 +1,120 @@
+//! Level files, the bundled ones and invalid ones.
+
+use gravinyon::{Level, LevelError};
+
+fn levels_directory() -> std::path::PathBuf {
+	std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("levels")
+}
+
+const VALID: &str = r#"
+name = "Test"
+
+[obstacles]
+initial_count = 3
+respawn_count = 1
+life = 2
+scale = [0.02, 0.04]
+spawn_y = [-0.4, 0.4]
+speed_x = [-0.003, 0.0005]
+speed_y = [-0.001, 0.001]
+rotation_speed = [-0.01, 0.01]
+
+[enemy_fire.at_cursor]
+range = 0.3
+interval = 10
+"#;
+
+fn invalid_reason(source: &str) -> String {
+	match Level::parse(source) {
+		Err(LevelError::Invalid(reason)) => reason,
+		Err(error) => panic!("not invalid but {error}"),
+		Ok(_) => panic!("the level is valid"),
+	}
+}
+
+#[test]
+fn bundled_levels() {
+	let bundled = Level::bundled();
+	let names: Vec<_> = bundled.iter().map(|level| level.name.as_str()).collect();
+	assert_eq!(names, ["One", "Two", "Three", "Four"]);
+	for (level, file_name) in bundled
+		.iter()
+		.zip(["one.toml", "two.toml", "three.toml", "four.toml"])
+	{
+		let loaded = Level::load(levels_directory().join(file_name)).unwrap();
+		assert_eq!(&loaded, level);
+		assert!(!level.description.is_empty());
+		// Written back, the level stays the same (replays rely on it).
+		assert_eq!(&Level::parse(&level.to_source()).unwrap(), level);
+	}
+
+	assert_eq!(Level::find_bundled("2").unwrap().name, "Two");
+	assert_eq!(Level::find_bundled("three").unwrap().name, "Three");
+	assert!(Level::find_bundled("0").is_none());
+	assert!(Level::find_bundled("5").is_none());
+	assert!(Level::find_bundled("Five").is_none());
+}
+
+#[test]
+fn optional_parts() {
+	let level = Level::parse(VALID).unwrap();
+	assert_eq!(level.description, "");
+	assert_eq!(level.obstacles.life, 2);
+	assert!(level.enemy_fire.at_ship.is_none());
+	assert!(level.enemy_fire.at_each_other.is_none());
+	let at_cursor = level.enemy_fire.at_cursor.unwrap();
+	assert_eq!((at_cursor.range, at_cursor.interval), (0.3, 10));
+}
+
+#[test]
+fn invalid_ranges() {
+	let reason =
+		invalid_reason(&VALID.replace("speed_x = [-0.003, 0.0005]", "speed_x = [0.1, 0.0]"));
+	assert_eq!(
+		reason,
+		"`obstacles.speed_x` must be two finite numbers, the first smaller than the second"
+	);
+	// An empty range is not a range either.
+	let reason = invalid_reason(&VALID.replace("spawn_y = [-0.4, 0.4]", "spawn_y = [0.2, 0.2]"));
+	assert!(reason.contains("`obstacles.spawn_y`"));
+	let reason = invalid_reason(&VALID.replace("scale = [0.02, 0.04]", "scale = [-0.02, 0.04]"));
+	assert_eq!(reason, "`obstacles.scale` must be positive");
+	let reason = invalid_reason(&VALID.replace("range = 0.3", "range = 0.0"));
+	assert_eq!(reason, "`enemy_fire.at_cursor.range` must be positive");
+}
+
+#[test]
+fn invalid_counts() {
+	let reason = invalid_reason(&VALID.replace("life = 2", "life = 0"));
+	assert_eq!(reason, "`obstacles.life` must be at least 1");
+	let reason = invalid_reason(&VALID.replace("interval = 10", "interval = 0"));
+	assert_eq!(reason, "`enemy_fire.at_cursor.interval` must be at least 1");
+	let reason = invalid_reason(&VALID.replace("initial_count = 3", "initial_count = 100000000"));
+	assert_eq!(reason, "`obstacles.initial_count` must be at most 200");
+	let reason = invalid_reason(&VALID.replace("respawn_count = 1", "respawn_count = 11"));
+	assert_eq!(reason, "`obstacles.respawn_count` must be at most 10");
+	assert!(Level::parse(&VALID.replace("initial_count = 3", "initial_count = 200")).is_ok());
+	// Counts can't be negative, that is already a parse error.
+	assert!(matches!(
+		Level::parse(&VALID.replace("initial_count = 3", "initial_count = -3")),
+		Err(LevelError::Parse(_))
+	));
+}
+
+#[test]
+fn malformed_levels() {
+	for source in [
+		"",
+		"name = \"Test\"",
+		&VALID.replace("life = 2", "life = 2\nlives = 3"),
+		&VALID.replace("[enemy_fire.at_cursor]", "[enemy_fire.at_mouse]"),
+		&VALID.replace("scale = [0.02, 0.04]", "scale = [0.02]"),
+	] {
+		assert!(
+			matches!(Level::parse(source), Err(LevelError::Parse(_))),
+			"{source}"
+		);
+	}
+	let error = Level::load(levels_directory().join("five.toml")).unwrap_err();
+	assert!(matches!(error, LevelError::Io(_)));
+}
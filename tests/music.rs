@@ -0,0 +1,199 @@
+//! The music following the intensity of the game, without ever needing an audio device.
+
+use std::{path::PathBuf, time::Duration};
+
+use gravinyon::{Intensity, Level, MusicLayer, SoundManager, SoundSettings, Synth, World};
+
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+fn play(sound_manager: &mut SoundManager, intensity: &Intensity, game_over: bool, seconds: u32) {
+	for _ in 0..seconds * 60 {
+		sound_manager.update_music(intensity, game_over, FRAME);
+	}
+}
+
+#[test]
+fn intensity() {
+	let world = World::new(Level::bundled().remove(0), 1);
+	let calm = Intensity::of_world(&world);
+	assert_eq!(calm.enemy_shots, 0);
+	assert!(calm.obstacles > 0);
+	assert!(calm.level() < 0.5);
+
+	let crowded = Intensity { obstacles: 20, ..calm };
+	let shot_at = Intensity { enemy_shots: 6, ..crowded };
+	let in_danger = Intensity { danger_distance: Some(0.05), ..shot_at };
+	assert!(calm.level() < crowded.level());
+	assert!(crowded.level() < shot_at.level());
+	assert!(shot_at.level() < in_danger.level());
+	assert!(in_danger.level() <= 1.0);
+	assert_eq!(Intensity::default().level(), 0.0);
+}
+
+#[test]
+fn layers_crossfade_with_the_intensity() {
+	let mut sound_manager = SoundManager::silent(SoundSettings::default(), "there/is/nothing/here");
+	let volumes = |sound_manager: &SoundManager| {
+		MusicLayer::ALL.map(|layer| sound_manager.music().layer_volume(layer))
+	};
+	assert_eq!(volumes(&sound_manager), [0.0, 0.0, 0.0]);
+
+	play(&mut sound_manager, &Intensity::default(), false, 3);
+	assert_eq!(volumes(&sound_manager), [1.0, 0.0, 0.0]);
+
+	let intense = Intensity { obstacles: 20, enemy_shots: 10, danger_distance: Some(0.0) };
+	// Layers fade in, they don't just pop in.
+	sound_manager.update_music(&intense, false, FRAME);
+	let [_, drive, danger] = volumes(&sound_manager);
+	assert!(0.0 < drive && drive < 0.1);
+	assert!(0.0 < danger && danger < 0.1);
+	play(&mut sound_manager, &intense, false, 3);
+	assert_eq!(volumes(&sound_manager), [1.0, 1.0, 1.0]);
+
+	let busy = Intensity { danger_distance: None, ..intense };
+	play(&mut sound_manager, &busy, false, 3);
+	let [base, drive, danger] = volumes(&sound_manager);
+	assert_eq!((base, drive), (1.0, 1.0));
+	assert!(danger < 1.0);
+}
+
+#[test]
+fn music_ducks_under_the_game_over() {
+	let mut sound_manager = SoundManager::silent(SoundSettings::default(), "there/is/nothing/here");
+	assert_eq!(sound_manager.music().ducking(), 1.0);
+	play(&mut sound_manager, &Intensity::default(), true, 1);
+	let ducking = sound_manager.music().ducking();
+	assert!(ducking < 0.5);
+	// The layers keep following the intensity under the ducking.
+	let base = sound_manager.music().layer_volume(MusicLayer::Base);
+	assert!((base - 0.5).abs() < 0.02);
+	play(&mut sound_manager, &Intensity::default(), false, 1);
+	assert_eq!(sound_manager.music().ducking(), 1.0);
+}
+
+#[test]
+fn sequenced_tracks() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), "there/is/nothing/here");
+	let tracks = MusicLayer::ALL.map(|layer| {
+		assert!(sound_manager.music().is_sequenced(layer));
+		layer.sequenced_track()
+	});
+	for track in &tracks {
+		// The layers loop together.
+		assert_eq!(track.samples.len(), tracks[0].samples.len());
+		assert_eq!(track.channels, 1);
+		let energy = track
+			.samples
+			.iter()
+			.map(|sample| sample * sample)
+			.sum::<f32>()
+			/ track.samples.len() as f32;
+		assert!(energy > 0.001);
+	}
+	let seconds = tracks[0].samples.len() as f32 / tracks[0].sample_rate as f32;
+	assert!((2.0..10.0).contains(&seconds));
+}
+
+const WAV_SAMPLE_RATE: u32 = 8000;
+
+/// A mono WAV file of the given samples (at `WAV_SAMPLE_RATE`), the music files are not required
+/// to be Ogg Vorbis ones.
+fn wav_file(samples: &[i16]) -> Vec<u8> {
+	let data_len = samples.len() as u32 * 2;
+	let mut bytes = Vec::new();
+	bytes.extend_from_slice(b"RIFF");
+	bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+	bytes.extend_from_slice(b"WAVEfmt ");
+	bytes.extend_from_slice(&16_u32.to_le_bytes());
+	// PCM, mono.
+	bytes.extend_from_slice(&1_u16.to_le_bytes());
+	bytes.extend_from_slice(&1_u16.to_le_bytes());
+	bytes.extend_from_slice(&WAV_SAMPLE_RATE.to_le_bytes());
+	bytes.extend_from_slice(&(WAV_SAMPLE_RATE * 2).to_le_bytes());
+	bytes.extend_from_slice(&2_u16.to_le_bytes());
+	bytes.extend_from_slice(&16_u16.to_le_bytes());
+	bytes.extend_from_slice(b"data");
+	bytes.extend_from_slice(&data_len.to_le_bytes());
+	for sample in samples {
+		bytes.extend_from_slice(&sample.to_le_bytes());
+	}
+	bytes
+}
+
+#[test]
+fn music_files() {
+	let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("music_files");
+	std::fs::create_dir_all(&directory).unwrap();
+	// A second of a ramp for the base layer, a quarter of a second of a constant for the danger
+	// layer.
+	let base: Vec<i16> = (0..WAV_SAMPLE_RATE as i16).map(|index| index * 4).collect();
+	let danger = vec![10000; WAV_SAMPLE_RATE as usize / 4];
+	std::fs::write(
+		directory.join(MusicLayer::Base.file_name()),
+		wav_file(&base),
+	)
+	.unwrap();
+	std::fs::write(
+		directory.join(MusicLayer::Drive.file_name()),
+		b"not music at all",
+	)
+	.unwrap();
+	std::fs::write(
+		directory.join(MusicLayer::Danger.file_name()),
+		wav_file(&danger),
+	)
+	.unwrap();
+
+	let sound_manager = SoundManager::silent(SoundSettings::default(), &directory);
+	let music = sound_manager.music();
+	// A file that can't be decoded is replaced by the sequenced track.
+	assert!(!music.is_sequenced(MusicLayer::Base));
+	assert!(music.is_sequenced(MusicLayer::Drive));
+	assert!(!music.is_sequenced(MusicLayer::Danger));
+
+	// All the layers loop on the duration of the base layer, the files being read again.
+	assert_eq!(music.loop_duration(), Duration::from_secs(1));
+	let loop_len = base.len();
+	let base_samples: Vec<f32> = music
+		.layer_source(MusicLayer::Base)
+		.take(loop_len * 3)
+		.collect();
+	assert!(base_samples[..loop_len] == base_samples[loop_len..(loop_len * 2)]);
+	assert!(base_samples[..loop_len] == base_samples[(loop_len * 2)..]);
+	assert!(base_samples[loop_len - 1] > 0.5);
+
+	// The danger layer is followed by silence until the loop starts again.
+	let danger_samples: Vec<f32> = music
+		.layer_source(MusicLayer::Danger)
+		.take(loop_len * 2)
+		.collect();
+	assert!(danger_samples[..danger.len()]
+		.iter()
+		.all(|&sample| sample > 0.2));
+	assert!(danger_samples[danger.len()..loop_len]
+		.iter()
+		.all(|&sample| sample == 0.0));
+	assert!(danger_samples[..loop_len] == danger_samples[loop_len..]);
+
+	// The drive layer is cut, its track being longer than a second.
+	let drive = music.layer_source(MusicLayer::Drive);
+	let drive_loop_len = Synth::SAMPLE_RATE as usize;
+	let drive_samples: Vec<f32> = drive.take(drive_loop_len * 2).collect();
+	assert!(drive_samples[..drive_loop_len] == drive_samples[drive_loop_len..]);
+	assert!(MusicLayer::Drive.sequenced_track().samples.len() > drive_loop_len);
+}
+
+#[test]
+fn sequenced_loop() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), "there/is/nothing/here");
+	let music = sound_manager.music();
+	let track = MusicLayer::Danger.sequenced_track();
+	let loop_duration = track.samples.len() as f64 / Synth::SAMPLE_RATE as f64;
+	assert!((music.loop_duration().as_secs_f64() - loop_duration).abs() < 1e-6);
+	let samples: Vec<f32> = music
+		.layer_source(MusicLayer::Danger)
+		.take(track.samples.len() * 2)
+		.collect();
+	assert_eq!(samples[..track.samples.len()], track.samples[..]);
+	assert_eq!(samples[track.samples.len()..], track.samples[..]);
+}
@@ -0,0 +1,159 @@
+//! The replay format, written and read back in memory.
+
+use gravinyon::{Input, Level, Replay, ReplayError, ReplayRecorder, World};
+
+fn scripted_inputs(steps: u32) -> Vec<Input> {
+	(0..steps)
+		.map(|step| {
+			let angle = step as f32 * 0.03;
+			Input {
+				// The cursor stays still for a while sometimes, which is recorded differently.
+				cursor_position: if step % 100 < 50 {
+					(angle.cos() * 0.5, angle.sin() * 0.3).into()
+				} else {
+					(0.2, -0.1).into()
+				},
+				shooting: step % 70 < 40,
+				impulse: step.is_multiple_of(33),
+				restart: step.is_multiple_of(250),
+			}
+		})
+		.collect()
+}
+
+fn record(level: &Level, seed: u64, inputs: &[Input]) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	let mut recorder = ReplayRecorder::new(&mut bytes, level, seed).unwrap();
+	for input in inputs {
+		recorder.record(input).unwrap();
+	}
+	recorder.flush().unwrap();
+	bytes
+}
+
+fn assert_same_inputs(inputs: &[Input], other_inputs: &[Input]) {
+	assert_eq!(inputs.len(), other_inputs.len());
+	for (input, other_input) in inputs.iter().zip(other_inputs) {
+		assert_eq!(input.cursor_position, other_input.cursor_position);
+		assert_eq!(input.shooting, other_input.shooting);
+		assert_eq!(input.impulse, other_input.impulse);
+		assert_eq!(input.restart, other_input.restart);
+	}
+}
+
+#[test]
+fn round_trip() {
+	// A level that is not bundled, it is embedded in the replay.
+	let mut level = Level::bundled().remove(2);
+	level.name = "Custom".to_string();
+	level.obstacles.initial_count = 9;
+	let inputs = scripted_inputs(600);
+	let bytes = record(&level, 1234, &inputs);
+
+	let replay = Replay::read(bytes.as_slice()).unwrap();
+	assert_eq!(replay.level, level);
+	assert_eq!(replay.seed, 1234);
+	assert_same_inputs(&replay.inputs, &inputs);
+}
+
+#[test]
+fn version_1_is_still_readable() {
+	let mut bytes = b"GRAVINYR".to_vec();
+	bytes.extend(1u16.to_le_bytes());
+	bytes.push(2);
+	bytes.extend(77u64.to_le_bytes());
+	// Shooting, then an impulse with the cursor moving, then a restart.
+	bytes.push(0b0001);
+	bytes.push(0b1010);
+	bytes.extend(0.25f32.to_le_bytes());
+	bytes.extend((-0.5f32).to_le_bytes());
+	bytes.push(0b0100);
+
+	let replay = Replay::read(bytes.as_slice()).unwrap();
+	assert_eq!(replay.level, Level::bundled()[1]);
+	assert_eq!(replay.seed, 77);
+	let expected_inputs = [
+		Input { shooting: true, ..Input::default() },
+		Input {
+			cursor_position: (0.25, -0.5).into(),
+			impulse: true,
+			..Input::default()
+		},
+		Input {
+			cursor_position: (0.25, -0.5).into(),
+			restart: true,
+			..Input::default()
+		},
+	];
+	assert_same_inputs(&replay.inputs, &expected_inputs);
+}
+
+#[test]
+fn invalid_replays() {
+	assert!(matches!(
+		Replay::read(&b"not a replay"[..]),
+		Err(ReplayError::NotAReplay)
+	));
+	let mut bytes = b"GRAVINYR".to_vec();
+	bytes.extend(9u16.to_le_bytes());
+	assert!(matches!(
+		Replay::read(bytes.as_slice()),
+		Err(ReplayError::UnsupportedVersion(9))
+	));
+
+	let level = &Level::bundled()[0];
+	let bytes = record(level, 5, &scripted_inputs(10));
+	let header_length = 8 + 2 + 4 + level.to_source().len() + 8;
+	// Cut in the middle of the seed, then in the middle of the cursor position of the last step.
+	assert!(matches!(
+		Replay::read(&bytes[..header_length - 3]),
+		Err(ReplayError::Truncated)
+	));
+	assert!(matches!(
+		Replay::read(&bytes[..bytes.len() - 2]),
+		Err(ReplayError::Truncated)
+	));
+	assert_eq!(
+		Replay::read(&bytes[..header_length]).unwrap().inputs.len(),
+		0
+	);
+	let mut bytes = bytes;
+	bytes.push(0b1000_0000);
+	assert!(matches!(
+		Replay::read(bytes.as_slice()),
+		Err(ReplayError::InvalidFlags(_))
+	));
+}
+
+#[test]
+fn replaying_gives_the_same_game() {
+	// Obstacles go down in one hit, so that the score moves.
+	let mut level = Level::bundled().remove(3);
+	level.obstacles.life = 1;
+	let inputs: Vec<_> = scripted_inputs(1500)
+		.into_iter()
+		.map(|input| Input { restart: false, ..input })
+		.collect();
+	let mut world = World::new(level.clone(), 99);
+	for input in &inputs {
+		world.step(input);
+	}
+
+	let replay = Replay::read(record(&level, 99, &inputs).as_slice()).unwrap();
+	let mut replayed_world = World::new(replay.level, replay.seed);
+	for input in &replay.inputs {
+		replayed_world.step(input);
+	}
+	assert!(world.score() > 0);
+	assert_eq!(replayed_world.score(), world.score());
+	assert_eq!(replayed_world.seed(), world.seed());
+	assert_eq!(replayed_world.is_game_over(), world.is_game_over());
+	let positions = |world: &World| {
+		world
+			.objects()
+			.iter()
+			.map(|object| object.position())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(positions(&replayed_world), positions(&world));
+}
@@ -0,0 +1,125 @@
+//! The high scores file, including when it is missing or corrupted.
+
+use std::{path::PathBuf, time::Duration};
+
+use gravinyon::{HighScores, Level, ScoreEntry};
+
+/// An empty directory of its own for the test.
+fn test_directory(name: &str) -> PathBuf {
+	let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+		.join("scores")
+		.join(name);
+	let _ = std::fs::remove_dir_all(&directory);
+	std::fs::create_dir_all(&directory).unwrap();
+	directory
+}
+
+fn entry(score: u32) -> ScoreEntry {
+	ScoreEntry::now(score, Duration::from_secs(12), Some("Tester".to_string()))
+}
+
+#[test]
+fn missing_file() {
+	let path = test_directory("missing").join("scores.json");
+	let high_scores = HighScores::load(&path);
+	assert!(high_scores.tables().next().is_none());
+	assert_eq!(high_scores.best("One", None), None);
+	// Nothing is written until there is something to save.
+	assert!(!path.exists());
+}
+
+#[test]
+fn corrupted_file() {
+	let directory = test_directory("corrupted");
+	let path = directory.join("scores.json");
+	std::fs::write(&path, "{ this is not the scores").unwrap();
+
+	let mut high_scores = HighScores::load(&path);
+	assert!(high_scores.tables().next().is_none());
+	// The corrupted file is put aside as it was, for the player to look at.
+	let corrupted_path = directory.join("scores.json.corrupted");
+	assert_eq!(
+		std::fs::read_to_string(corrupted_path).unwrap(),
+		"{ this is not the scores"
+	);
+	assert!(!path.exists());
+
+	// Starting anew works.
+	high_scores.add("One", None, entry(5));
+	high_scores.save().unwrap();
+	assert_eq!(HighScores::load(&path).best("One", None), Some(5));
+}
+
+#[test]
+fn save_and_load() {
+	// The directory is created when saving.
+	let directory = test_directory("round_trip").join("nested");
+	let path = directory.join("scores.json");
+	let mut high_scores = HighScores::load(&path);
+	assert_eq!(high_scores.add("One", None, entry(10)), Some(0));
+	assert_eq!(high_scores.add("One", None, entry(30)), Some(0));
+	assert_eq!(high_scores.add("One", None, entry(20)), Some(1));
+	assert_eq!(high_scores.add("One", Some(7), entry(1)), Some(0));
+	assert_eq!(
+		high_scores.add("Two", None, ScoreEntry::now(3, Duration::ZERO, None)),
+		Some(0)
+	);
+	high_scores.save().unwrap();
+	// The file is replaced at once, through a temporary file that does not stay around.
+	assert!(!directory.join("scores.json.tmp").exists());
+
+	let loaded = HighScores::load(&path);
+	let tables: Vec<_> = loaded.tables().collect();
+	let original_tables: Vec<_> = high_scores.tables().collect();
+	assert_eq!(tables, original_tables);
+	let scores: Vec<_> = loaded
+		.entries("One", None)
+		.iter()
+		.map(|entry| entry.score)
+		.collect();
+	assert_eq!(scores, [30, 20, 10]);
+	assert_eq!(loaded.best("One", Some(7)), Some(1));
+	assert_eq!(loaded.entries("Two", None)[0].name, None);
+
+	// Saving again replaces the previous file.
+	let mut high_scores = loaded;
+	high_scores.add("One", None, entry(40));
+	high_scores.save().unwrap();
+	assert_eq!(HighScores::load(&path).best("One", None), Some(40));
+}
+
+#[test]
+fn tables_keep_the_best_entries() {
+	let mut high_scores = HighScores::in_memory();
+	for score in 1..=HighScores::TABLE_LEN as u32 {
+		high_scores.add("One", None, entry(score * 10));
+	}
+	assert_eq!(high_scores.add("One", None, entry(5)), None);
+	// An equal score goes after the older entries.
+	assert_eq!(high_scores.add("One", None, entry(50)), Some(6));
+	let entries = high_scores.entries("One", None);
+	assert_eq!(entries.len(), HighScores::TABLE_LEN);
+	assert_eq!(entries.last().unwrap().score, 20);
+	// Scores in memory are not saved anywhere.
+	high_scores.save().unwrap();
+}
+
+#[test]
+fn custom_levels_have_their_own_tables() {
+	let bundled = Level::find_bundled("1").unwrap();
+	assert_eq!(bundled.high_scores_key(), bundled.name);
+	// A level file that has the name of a bundled level, but not its content.
+	let mut custom = bundled.clone();
+	custom.obstacles.life += 1;
+	let custom_key = custom.high_scores_key();
+	assert_ne!(custom_key, bundled.name);
+	assert_eq!(custom_key, custom.clone().high_scores_key());
+	let mut other_custom = custom.clone();
+	other_custom.obstacles.life += 1;
+	assert_ne!(other_custom.high_scores_key(), custom_key);
+
+	let mut high_scores = HighScores::in_memory();
+	high_scores.add(&custom_key, None, entry(1000));
+	assert_eq!(high_scores.best(&bundled.high_scores_key(), None), None);
+	assert_eq!(high_scores.best(&custom_key, None), Some(1000));
+}
@@ -0,0 +1,184 @@
+//! The sound manager, without ever needing an audio device.
+
+use std::collections::BTreeMap;
+
+use gravinyon::{Config, SoundEffect, SoundManager, SoundSettings, WorldEvent};
+
+fn sounds_directory() -> std::path::PathBuf {
+	std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/sounds")
+}
+
+#[test]
+fn effects_are_decoded_once() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), sounds_directory());
+	assert!(!sound_manager.has_output());
+	// Only the shot comes with a file, the other effects are synthesized.
+	for effect in SoundEffect::ALL {
+		assert_eq!(
+			sound_manager.is_synthesized(effect),
+			effect != SoundEffect::Shot,
+			"{effect:?}"
+		);
+	}
+	// Playing without an audio device does nothing.
+	sound_manager.play(SoundEffect::Shot);
+}
+
+#[test]
+fn missing_files_are_not_fatal() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), "there/is/nothing/here");
+	assert!(sound_manager.is_synthesized(SoundEffect::Shot));
+	sound_manager.play(SoundEffect::Shot);
+}
+
+#[test]
+fn volume_settings() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), sounds_directory());
+	let full_volume = sound_manager.volume(SoundEffect::Shot);
+	assert!(full_volume > 0.0);
+
+	let settings = SoundSettings {
+		master_volume: 0.5,
+		effects_volume: 0.5,
+		..SoundSettings::default()
+	};
+	let mut sound_manager = SoundManager::silent(settings, sounds_directory());
+	assert!((sound_manager.volume(SoundEffect::Shot) - full_volume * 0.25).abs() < 1e-6);
+	sound_manager.set_muted(true);
+	assert_eq!(sound_manager.volume(SoundEffect::Shot), 0.0);
+	sound_manager.set_muted(false);
+	assert!((sound_manager.volume(SoundEffect::Shot) - full_volume * 0.25).abs() < 1e-6);
+}
+
+#[test]
+fn sound_config() {
+	let config = Config::parse("[sound]\nmaster_volume = 0.8\nmute = true\n").unwrap();
+	assert_eq!(
+		config.sound,
+		SoundSettings {
+			master_volume: 0.8,
+			effects_volume: 1.0,
+			mute: true,
+			..SoundSettings::default()
+		}
+	);
+	assert!(Config::parse("[sound]\neffects_volume = 1.5")
+		.unwrap_err()
+		.to_string()
+		.contains("`effects_volume` in `sound` must be between 0 and 1"));
+	assert!(Config::parse("[sound]\nmusic_volume = -0.5")
+		.unwrap_err()
+		.to_string()
+		.contains("`music_volume` in `sound` must be between 0 and 1"));
+	assert!(Config::parse("[sound]\nvolume = 1").is_err());
+}
+
+#[test]
+fn sound_files() {
+	let config =
+		Config::parse("[sound.files]\nhit = \"pew.wav\"\ngame_over = \"nope.wav\"\n").unwrap();
+	assert_eq!(
+		config.sound.files,
+		BTreeMap::from([
+			(SoundEffect::Hit, "pew.wav".into()),
+			(SoundEffect::GameOver, "nope.wav".into())
+		])
+	);
+	assert!(Config::parse("[sound.files]\nbonk = \"bonk.wav\"").is_err());
+
+	// The files of the config replace the default ones, a missing one is not fatal.
+	let sound_manager = SoundManager::silent(config.sound, sounds_directory());
+	assert!(!sound_manager.is_synthesized(SoundEffect::Shot));
+	assert!(!sound_manager.is_synthesized(SoundEffect::Hit));
+	assert!(sound_manager.is_synthesized(SoundEffect::GameOver));
+	assert!(sound_manager.is_synthesized(SoundEffect::Explosion));
+}
+
+#[test]
+fn each_event_has_its_effect() {
+	let position = (0.0, 0.0).into();
+	let effects: Vec<_> = [
+		WorldEvent::ShotFired { position },
+		WorldEvent::ShotHit { position },
+		WorldEvent::ObstacleDestroyed { position },
+		WorldEvent::EnemyShotSpawned { position },
+		WorldEvent::Impulse { position },
+		WorldEvent::GameOver { position },
+		WorldEvent::Restart,
+	]
+	.iter()
+	.map(SoundEffect::of_event)
+	.collect();
+	assert_eq!(effects, SoundEffect::ALL);
+}
+
+#[test]
+fn voices_are_heard_from_the_ship() {
+	let sound_manager = SoundManager::silent(SoundSettings::default(), sounds_directory());
+	let ship = (0.0, 0.0).into();
+	let here = sound_manager.voice(SoundEffect::Explosion, Some(ship), ship);
+	assert_eq!(here.left_volume, here.right_volume);
+	assert!((here.left_volume - sound_manager.volume(SoundEffect::Explosion)).abs() < 1e-6);
+
+	// Effects on the left are heard more on the left, and they are quieter farther away.
+	let left = sound_manager.voice(SoundEffect::Explosion, Some((-0.5, 0.0).into()), ship);
+	assert!(left.left_volume > left.right_volume);
+	assert!(left.left_volume < here.left_volume);
+	let far_left = sound_manager.voice(SoundEffect::Explosion, Some((-1.0, 0.0).into()), ship);
+	assert!(far_left.left_volume < left.left_volume);
+	assert!(far_left.right_volume > 0.0);
+	let right = sound_manager.voice(SoundEffect::Explosion, Some((0.5, 0.0).into()), ship);
+	assert!(right.right_volume > right.left_volume);
+
+	// Repeated effects don't all have the same pitch.
+	let speeds: Vec<_> = (0..50)
+		.map(|_| {
+			sound_manager
+				.voice(SoundEffect::Shot, Some(ship), ship)
+				.speed
+		})
+		.collect();
+	assert!(speeds.iter().all(|speed| (0.9..1.1).contains(speed)));
+	assert!(speeds.iter().any(|speed| *speed != speeds[0]));
+}
+
+#[test]
+fn voices_per_frame_are_capped() {
+	let mut sound_manager = SoundManager::silent(SoundSettings::default(), sounds_directory());
+	let ship = (0.0, 0.0).into();
+	let far = (1.0, 0.3).into();
+	let mut events = vec![WorldEvent::EnemyShotSpawned { position: far }; 30];
+	events.push(WorldEvent::EnemyShotSpawned { position: ship });
+	events.push(WorldEvent::ShotFired { position: ship });
+	let voices = sound_manager.voices(&events, ship);
+	// An effect starts at most once per frame, the loudest one being kept.
+	assert_eq!(voices.len(), 2);
+	let enemy_shot = voices
+		.iter()
+		.find(|voice| voice.effect == SoundEffect::EnemyShot)
+		.unwrap();
+	assert_eq!(enemy_shot.left_volume, enemy_shot.right_volume);
+
+	let position = far;
+	let events = [
+		WorldEvent::ShotFired { position },
+		WorldEvent::ShotHit { position },
+		WorldEvent::ObstacleDestroyed { position },
+		WorldEvent::EnemyShotSpawned { position },
+		WorldEvent::Impulse { position },
+		WorldEvent::GameOver { position },
+		WorldEvent::Restart,
+	];
+	let voices = sound_manager.voices(&events, ship);
+	assert_eq!(voices.len(), SoundManager::MAX_VOICES_PER_FRAME);
+	assert!(voices
+		.windows(2)
+		.all(|pair| pair[0].left_volume.max(pair[0].right_volume)
+			>= pair[1].left_volume.max(pair[1].right_volume)));
+
+	// Nothing plays without an audio device, and nothing is heard when muted.
+	sound_manager.play_events(&events, ship);
+	assert_eq!(sound_manager.playing_voices(), 0);
+	sound_manager.set_muted(true);
+	assert!(sound_manager.voices(&events, ship).is_empty());
+}
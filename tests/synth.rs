@@ -0,0 +1,110 @@
+//! The synthesizer, rendered to buffers of samples.
+
+use gravinyon::{Envelope, SoundEffect, Synth, Waveform};
+use rodio::Source;
+
+const FLAT: Envelope = Envelope {
+	attack: 0.0,
+	decay: 0.0,
+	sustain_level: 1.0,
+	sustain: 0.5,
+	release: 0.0,
+};
+
+fn tone(waveform: Waveform, start_frequency: f32, end_frequency: f32) -> Synth {
+	Synth {
+		waveform,
+		start_frequency,
+		end_frequency,
+		noise: 0.0,
+		envelope: FLAT,
+	}
+}
+
+/// How many times the samples go from negative to non-negative.
+fn rising_zero_crossings(samples: &[f32]) -> usize {
+	samples
+		.windows(2)
+		.filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+		.count()
+}
+
+#[test]
+fn oscillators() {
+	for waveform in [
+		Waveform::Sine,
+		Waveform::Square,
+		Waveform::Triangle,
+		Waveform::Sawtooth,
+	] {
+		let synth = tone(waveform, 440.0, 440.0);
+		let samples = synth.render();
+		assert_eq!(samples.len(), Synth::SAMPLE_RATE as usize / 2);
+		assert!(samples.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+		// Half a second of a 440 Hz wave.
+		let periods = rising_zero_crossings(&samples);
+		assert!((219..=221).contains(&periods), "{waveform:?}: {periods}");
+	}
+}
+
+#[test]
+fn pitch_sweep() {
+	let samples = tone(Waveform::Sine, 1000.0, 100.0).render();
+	let (start, end) = samples.split_at(samples.len() / 2);
+	assert!(rising_zero_crossings(start) > 2 * rising_zero_crossings(end));
+}
+
+#[test]
+fn envelope() {
+	let envelope = Envelope {
+		attack: 0.1,
+		decay: 0.1,
+		sustain_level: 0.5,
+		sustain: 0.2,
+		release: 0.1,
+	};
+	assert_eq!(envelope.duration(), 0.5);
+	assert_eq!(envelope.volume(0.0), 0.0);
+	assert!((envelope.volume(0.1) - 1.0).abs() < 1e-6);
+	assert!((envelope.volume(0.3) - 0.5).abs() < 1e-6);
+	assert!((envelope.volume(0.45) - 0.25).abs() < 1e-6);
+	assert_eq!(envelope.volume(0.6), 0.0);
+
+	let samples = Synth { envelope, ..tone(Waveform::Square, 100.0, 100.0) }.render();
+	let peak = |samples: &[f32]| {
+		samples
+			.iter()
+			.fold(0.0, |peak: f32, sample| peak.max(sample.abs()))
+	};
+	let rate = Synth::SAMPLE_RATE as usize;
+	assert!(peak(&samples[..rate / 100]) < 0.15);
+	assert!((peak(&samples[rate / 4..rate * 3 / 8]) - 0.5).abs() < 1e-3);
+	assert!(peak(&samples[samples.len() - rate / 100..]) < 0.15);
+}
+
+#[test]
+fn noise() {
+	let synth = Synth { noise: 1.0, ..tone(Waveform::Sine, 440.0, 440.0) };
+	let samples = synth.render();
+	assert!(samples.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+	let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+	assert!(mean.abs() < 0.05);
+	// Far more zero crossings than the tone would have, and the same noise every time.
+	assert!(rising_zero_crossings(&samples) > 2000);
+	assert_eq!(samples, synth.render());
+}
+
+#[test]
+fn effects_have_sounds() {
+	for effect in SoundEffect::ALL {
+		let synth = effect.synth();
+		let source = synth.source();
+		assert_eq!(source.channels(), 1);
+		assert_eq!(source.total_duration(), Some(synth.duration()));
+		let samples = synth.render();
+		assert!(!samples.is_empty(), "{effect:?}");
+		assert!(samples.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+		let energy = samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+		assert!(energy > 0.01, "{effect:?} is almost silent");
+	}
+}
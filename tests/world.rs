@@ -0,0 +1,140 @@
+//! Steps the simulation headless, as `run` does between two frames.
+
+use gravinyon::{Input, Level, Object, World, WorldEvent};
+
+/// A level with a single obstacle, that spawns on the right at the height of the ship and goes
+/// straight at it.
+fn head_on_level() -> Level {
+	let mut level = Level::bundled().remove(0);
+	level.obstacles.initial_count = 1;
+	level.obstacles.scale = [0.04, 0.0401];
+	level.obstacles.spawn_y = [0.0, 0.0001];
+	level.obstacles.speed_x = [-0.02, -0.0199];
+	level.obstacles.speed_y = [0.0, 0.0001];
+	level
+}
+
+fn ship_motion(world: &World) -> cgmath::Vector2<f32> {
+	match world.objects()[0] {
+		Object::Ship { motion, .. } => motion,
+		_ => panic!("the ship is not the first object"),
+	}
+}
+
+#[test]
+fn impulse_moves_the_ship_towards_the_cursor() {
+	let mut world = World::new(Level::bundled().remove(0), 1);
+	let start = world.objects()[0].position();
+	assert_eq!(ship_motion(&world), (0.0, 0.0).into());
+
+	let input = Input {
+		cursor_position: (0.0, 0.4).into(),
+		impulse: true,
+		..Input::default()
+	};
+	world.step(&input);
+	let motion = ship_motion(&world);
+	assert!(motion.y > 0.0);
+	assert!(motion.x.abs() < 1e-6);
+
+	// Without any more impulse, the ship keeps going (and the cursor keeps pulling it a bit).
+	let input = Input { impulse: false, ..input };
+	for _ in 0..10 {
+		world.step(&input);
+	}
+	assert!(ship_motion(&world).y >= motion.y);
+	assert!(world.objects()[0].position().y > start.y);
+	assert!(!world.is_game_over());
+}
+
+#[test]
+fn obstacle_collision_is_game_over() {
+	let mut world = World::new(head_on_level(), 1);
+	assert_eq!(world.objects().len(), 2);
+	// The ship stays still, with the cursor on it.
+	let mut events = Vec::new();
+	for _ in 0..200 {
+		world.step(&Input::default());
+		events.extend(world.take_events());
+		if world.is_game_over() {
+			break;
+		}
+	}
+	assert!(world.is_game_over());
+	assert_eq!(world.score(), 0);
+	assert!(matches!(
+		events.as_slice(),
+		[WorldEvent::GameOver { position }] if *position == (0.0, 0.0).into()
+	));
+
+	// Nothing moves anymore until a restart.
+	let positions: Vec<_> = world.objects().iter().map(Object::position).collect();
+	world.step(&Input::default());
+	let positions_after: Vec<_> = world.objects().iter().map(Object::position).collect();
+	assert_eq!(positions, positions_after);
+}
+
+/// Some inputs that do a bit of everything, the same every time.
+fn scripted_input(step: u32) -> Input {
+	let angle = step as f32 * 0.02;
+	Input {
+		cursor_position: (angle.cos() * 0.6, angle.sin() * 0.3).into(),
+		shooting: step % 90 < 60,
+		impulse: step.is_multiple_of(45),
+		restart: step.is_multiple_of(200),
+	}
+}
+
+/// What can be observed of a world after some steps.
+#[derive(PartialEq, Debug)]
+struct Outcome {
+	score: u32,
+	seed: u64,
+	game_over: bool,
+	positions: Vec<cgmath::Point2<f32>>,
+	events: Vec<WorldEvent>,
+}
+
+fn play(seed: u64, steps: u32) -> Outcome {
+	let mut world = World::new(Level::bundled().remove(3), seed);
+	let mut events = Vec::new();
+	for step in 0..steps {
+		world.step(&scripted_input(step));
+		events.extend(world.take_events());
+	}
+	Outcome {
+		score: world.score(),
+		seed: world.seed(),
+		game_over: world.is_game_over(),
+		positions: world.objects().iter().map(Object::position).collect(),
+		events,
+	}
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_game() {
+	let outcome = play(42, 2000);
+	assert!(outcome
+		.events
+		.iter()
+		.any(|event| matches!(event, WorldEvent::ShotHit { .. })));
+	assert_eq!(outcome, play(42, 2000));
+}
+
+#[test]
+fn different_seeds_give_different_games() {
+	let outcome = play(42, 300);
+	let other_outcome = play(43, 300);
+	assert_ne!(outcome.positions, other_outcome.positions);
+	assert_ne!(outcome, other_outcome);
+	// The obstacles are not laid out the same from the very beginning.
+	let layout = |seed| {
+		World::new(Level::bundled().remove(0), seed)
+			.objects()
+			.iter()
+			.map(Object::position)
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(layout(7), layout(7));
+	assert_ne!(layout(7), layout(8));
+}
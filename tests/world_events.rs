@@ -0,0 +1,80 @@
+//! The events the simulation emits, as seen by a headless run.
+
+use gravinyon::{Input, Level, World, WorldEvent};
+
+/// Runs the world with the same input for the given number of steps, and gives the events of
+/// each step.
+fn run(world: &mut World, input: Input, steps: usize) -> Vec<Vec<WorldEvent>> {
+	(0..steps)
+		.map(|_| {
+			world.step(&input);
+			world.take_events()
+		})
+		.collect()
+}
+
+#[test]
+fn shots_hit_and_destroy_obstacles() {
+	let mut world = World::new(Level::bundled().remove(0), 1);
+	let input = Input {
+		cursor_position: (0.3, 0.0).into(),
+		shooting: true,
+		..Input::default()
+	};
+	let events: Vec<_> = run(&mut world, input, 3000).concat();
+	assert!(matches!(events[0], WorldEvent::ShotFired { .. }));
+	let count = |is_it: fn(&WorldEvent) -> bool| events.iter().filter(|event| is_it(event)).count();
+	assert!(count(|event| matches!(event, WorldEvent::ShotHit { .. })) > 0);
+	assert_eq!(
+		count(|event| matches!(event, WorldEvent::ObstacleDestroyed { .. })),
+		world.score() as usize
+	);
+	assert_eq!(
+		count(|event| matches!(event, WorldEvent::EnemyShotSpawned { .. })),
+		0
+	);
+	assert_eq!(
+		count(|event| matches!(event, WorldEvent::Impulse { .. })),
+		0
+	);
+
+	// Taking the events empties the queue.
+	assert!(world.take_events().is_empty());
+}
+
+#[test]
+fn enemy_fire() {
+	let mut world = World::new(Level::bundled().remove(1), 1);
+	let events = run(&mut world, Input::default(), 3000).concat();
+	assert!(events
+		.iter()
+		.any(|event| matches!(event, WorldEvent::EnemyShotSpawned { .. })));
+}
+
+#[test]
+fn game_over_and_restart() {
+	let mut world = World::new(Level::bundled().remove(0), 1);
+	// The ship rushes into the obstacles.
+	let input = Input {
+		cursor_position: (1.0, 0.0).into(),
+		impulse: true,
+		..Input::default()
+	};
+	let steps = run(&mut world, input, 100);
+	let game_over_step = steps
+		.iter()
+		.position(|events| {
+			events
+				.iter()
+				.any(|event| matches!(event, WorldEvent::GameOver { .. }))
+		})
+		.expect("the ship survived");
+	assert!(world.is_game_over());
+	assert!(matches!(steps[0][0], WorldEvent::Impulse { .. }));
+	// The game over happens once, and nothing happens afterwards.
+	assert!(steps[game_over_step + 1..].iter().all(Vec::is_empty));
+
+	let restart_events = run(&mut world, Input { restart: true, ..input }, 1).concat();
+	assert_eq!(restart_events, [WorldEvent::Restart]);
+	assert!(!world.is_game_over());
+}